@@ -1,60 +1,224 @@
-use clap::Parser;
-use url::Url;
+use clap::{Args as ClapArgs, Parser, Subcommand};
 
 use crate::{config, files, site::ArticleSlugStyle};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub(crate) command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Build the site and write it to the configured output
+    Generate(GenerateArgs),
+    /// Build the site and serve it locally
+    Serve(ServeArgs),
+    /// Load and render the site without writing anything
+    Check(Args),
+    /// Scaffold a new site or post
+    #[command(subcommand)]
+    New(NewCommand),
+}
+
+#[derive(ClapArgs)]
+pub struct GenerateArgs {
+    #[command(flatten)]
+    pub(crate) site: Args,
+    /// Keep running and rebuild whenever content, templates or assets change
+    #[arg(long, default_value_t = false)]
+    pub(crate) watch: bool,
+}
+
+#[derive(ClapArgs)]
+pub struct ServeArgs {
+    #[command(flatten)]
+    pub(crate) site: Args,
+    #[arg(long, value_name = "ADDR")]
+    listen: Option<String>,
+    #[arg(long, value_name = "URL")]
+    public_url: Option<String>,
+    #[arg(long, default_value_t = false)]
+    no_reload: bool,
+}
+
+impl ServeArgs {
+    pub fn make_config(mut self) -> crate::Result<config::Configuration> {
+        let listen = self.listen.take();
+        let public_url = self.public_url.take();
+        let no_reload = self.no_reload;
+        let mut conf = self.site.make_config()?;
+        if let Some(listen) = listen {
+            conf.serve.listen = listen;
+        }
+        if public_url.is_some() {
+            conf.serve.public_url = public_url;
+        }
+        if no_reload {
+            conf.serve.reload = false;
+        }
+        // links need to point at the local server rather than wherever the site is deployed
+        conf.site.base_url = conf.serve.base_url()?;
+        Ok(conf)
+    }
+}
+
+#[derive(Subcommand)]
+pub enum NewCommand {
+    /// Create a content directory, templates and config.yaml
+    Site {
+        #[arg(value_name = "DIR")]
+        path: std::path::PathBuf,
+    },
+    /// Create a new markdown post in the content directory
+    Post {
+        #[arg(value_name = "TITLE")]
+        title: String,
+        #[command(flatten)]
+        site: Args,
+    },
+}
+
+#[derive(ClapArgs)]
 pub struct Args {
+    #[arg(long, value_name = "CONFIG")]
+    config: Option<std::path::PathBuf>,
     #[arg(short = 'C', value_name = "CONTENT")]
-    content_path: std::path::PathBuf,
+    content_path: Option<std::path::PathBuf>,
     #[arg(short = 'O', value_name = "OUTPUT")]
-    output: std::path::PathBuf,
+    output: Option<std::path::PathBuf>,
     #[arg(short = 'U', value_name = "URL")]
-    url_base: String,
+    url_base: Option<String>,
     #[arg(short = 'T', value_name = "TEMPLATES")]
-    template_path: std::path::PathBuf,
+    template_path: Option<std::path::PathBuf>,
     #[arg(short = 'P', value_name = "PAGE_ROOT_PATH")]
     page_root: Option<String>,
     #[arg(short = 'D', default_value_t = false)]
     write_directories: bool,
-    #[arg(long, default_value_t = true)]
-    clean: bool,
+    #[arg(short = 'S', default_value_t = false)]
+    preserve_structure: bool,
+    #[arg(long, default_value_t = false)]
+    include_hidden: bool,
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    clean: Option<bool>,
     #[arg(long)]
     assets: Option<std::path::PathBuf>,
+    #[arg(long, value_name = "CACHE_DIR")]
+    cache: Option<std::path::PathBuf>,
+    #[arg(long, default_value_t = false)]
+    no_cache: bool,
 }
 
 impl Args {
-    pub fn make_config(mut self) -> config::Configuration {
-        config::Configuration {
+    pub fn make_config(mut self) -> crate::Result<config::Configuration> {
+        let mut conf = match self.config.take() {
+            Some(path) => config::Configuration::load(path)?,
+            None => self.bare_config()?,
+        };
+        self.apply(&mut conf)?;
+        Ok(conf)
+    }
+
+    // `new post` only cares about where the content lives
+    pub fn content_dir(mut self) -> crate::Result<files::DirPath> {
+        match (self.content_path.take(), self.config.take()) {
+            (Some(content), _) => Ok(unsafe { files::DirPath::new(content) }),
+            (None, Some(path)) => Ok(config::Configuration::load(path)?.content.base()),
+            (None, None) => required(None, "-C"),
+        }
+    }
+
+    // without a config file every path has to come from the command line
+    fn bare_config(&mut self) -> crate::Result<config::Configuration> {
+        Ok(config::Configuration {
             content: config::Content {
-                base: unsafe { files::DirPath::new(self.content_path) },
-                assets: self.assets.map(|a| unsafe { files::DirPath::new(a) }),
+                base: unsafe { files::DirPath::new(required(self.content_path.take(), "-C")?) },
+                include: Default::default(),
+                exclude: Default::default(),
+                include_hidden: false,
+                pages: Default::default(),
+                preserve_structure: false,
+                raw_html: Default::default(),
             },
             site: config::Site {
-                templates: unsafe { files::DirPath::new(self.template_path) },
-                base_url: Url::parse(&self.url_base).expect("invalid base url"),
+                templates: unsafe {
+                    files::DirPath::new(required(self.template_path.take(), "-T")?)
+                },
+                base_url: config::parse_base_url(required(self.url_base.take(), "-U")?, true)?,
             },
             output: config::Output {
-                output: match self.output.extension().map(|ext| ext.to_str().unwrap()) {
-                    Some(ext) if ext == "gz" || ext == "tar" => {
-                        files::Path::File(unsafe { files::FilePath::new(self.output) })
-                    }
-                    _ => files::Path::Dir(unsafe { files::DirPath::new(self.output) }),
-                },
-                clean: self.clean,
+                output: config::output_path(required(self.output.take(), "-O")?),
+                clean: true,
             },
             rendering: config::Rendering {
-                slug_style: if self.write_directories {
-                    ArticleSlugStyle::Directory
-                } else {
-                    ArticleSlugStyle::Page
-                },
-                page_root: self
-                    .page_root
-                    .take()
-                    .map(|root| unsafe { files::DirPath::new(root) }),
+                slug_style: ArticleSlugStyle::Page,
+                page_root: None,
+                highlighting: Default::default(),
+                heading_anchors: false,
+                localization: Default::default(),
             },
+            transformers: Default::default(),
+            taxonomies: vec![config::Taxonomy::tags(
+                &Default::default(),
+                &Default::default(),
+            )],
+            serve: Default::default(),
+            cache: Some(config::DEFAULT_CACHE.into()),
+        })
+    }
+
+    // anything passed on the command line wins over the config file
+    fn apply(mut self, conf: &mut config::Configuration) -> crate::Result<()> {
+        if let Some(content) = self.content_path.take() {
+            conf.content.base = unsafe { files::DirPath::new(content) };
+        }
+        if let Some(assets) = self.assets.take() {
+            conf.content.include.push(config::Include {
+                path: files::Path::parse(assets).map_err(|p| {
+                    Box::new(files::PathError::Unsupported(p))
+                        as Box<dyn std::error::Error + Send + Sync>
+                })?,
+                as_: None,
+            });
+        }
+        if let Some(templates) = self.template_path.take() {
+            conf.site.templates = unsafe { files::DirPath::new(templates) };
+        }
+        if let Some(url) = self.url_base.take() {
+            conf.site.base_url = config::parse_base_url(url, true)?;
+        }
+        if let Some(output) = self.output.take() {
+            conf.output.output = config::output_path(output);
         }
+        if let Some(clean) = self.clean {
+            conf.output.clean = clean;
+        }
+        if self.write_directories {
+            conf.rendering.slug_style = ArticleSlugStyle::Directory;
+        }
+        if self.preserve_structure {
+            conf.content.preserve_structure = true;
+        }
+        if self.include_hidden {
+            conf.content.include_hidden = true;
+        }
+        if let Some(cache) = self.cache.take() {
+            conf.cache = Some(cache);
+        }
+        if self.no_cache {
+            conf.cache = None;
+        }
+        if let Some(root) = self.page_root.take() {
+            conf.rendering.page_root = Some(unsafe { files::DirPath::new(root) });
+        }
+        Ok(())
     }
 }
+
+fn required<T>(value: Option<T>, flag: &str) -> crate::Result<T> {
+    value.ok_or_else(|| {
+        Box::new(config::Error::Missing(format!("{flag} (or --config)")))
+            as Box<dyn std::error::Error + Send + Sync>
+    })
+}
@@ -1,45 +1,552 @@
 use url::Url;
+use yaml_rust2::{Yaml, YamlLoader};
 
-use crate::{files, site, writers};
-use std::fs;
+use crate::{content, files, md, processors, render, site, writers};
+use std::{collections::BTreeMap, fmt::Display, fs, path};
 
-#[derive(Clone, Debug)]
+pub const DEFAULT_CACHE: &str = ".donter-cache";
+
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct Configuration {
     pub(crate) content: Content,
     pub(crate) site: Site,
     pub(crate) output: Output,
     pub(crate) rendering: Rendering,
+    pub(crate) transformers: Transformers,
+    pub(crate) taxonomies: Vec<Taxonomy>,
+    pub(crate) serve: Serve,
+    // where parsed pages and rendered output are kept between builds
+    pub(crate) cache: Option<path::PathBuf>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct Content {
     pub(crate) base: files::DirPath,
-    pub(crate) assets: Option<files::DirPath>,
+    pub(crate) include: Vec<Include>,
+    // globs, relative to the config file, for anything walked that should be skipped
+    pub(crate) exclude: Vec<String>,
+    // whether dot files are walked or skipped
+    pub(crate) include_hidden: bool,
+    // globs, relative to the config file, a page has to match to be loaded
+    pub(crate) pages: Vec<String>,
+    pub(crate) preserve_structure: bool,
+    // what to do with html written directly in pages
+    pub(crate) raw_html: md::HtmlPolicy,
 }
 impl Content {
     pub fn base(&self) -> files::DirPath {
         self.base.clone()
     }
+
+    pub fn page_filter(&self) -> crate::Result<files::Filter> {
+        self.asset_filter()?.include(&self.pages)
+    }
+
+    pub fn asset_filter(&self) -> crate::Result<files::Filter> {
+        Ok(files::Filter::new(&self.exclude)?.include_hidden(self.include_hidden))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Include {
+    pub(crate) path: files::Path,
+    pub(crate) as_: Option<String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct Site {
     pub(crate) templates: files::DirPath,
     pub(crate) base_url: Url,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct Rendering {
     pub(crate) slug_style: site::ArticleSlugStyle,
     pub(crate) page_root: Option<files::DirPath>,
+    pub(crate) highlighting: Highlighting,
+    pub(crate) heading_anchors: bool,
+    pub(crate) localization: Localization,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Localization {
+    // strftime, what `date` formats with unless it's given a pattern
+    pub(crate) date_pattern: String,
+    #[serde(serialize_with = "tz_name")]
+    pub(crate) timezone: chrono_tz::Tz,
+}
+
+fn tz_name<S: serde::Serializer>(tz: &chrono_tz::Tz, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(tz.name())
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self {
+            date_pattern: "%Y-%m-%d".to_owned(),
+            timezone: chrono_tz::UTC,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub enum Highlighting {
+    // code is escaped and otherwise left alone
+    #[default]
+    Off,
+    On {
+        theme: String,
+        style: render::HighlightStyle,
+    },
+}
+
+impl Rendering {
+    // feeds can't link the stylesheet so code in them is colored inline
+    pub fn inline_highlighter(&self) -> crate::Result<Box<dyn render::CodeHighlighter>> {
+        Ok(match &self.highlighting {
+            Highlighting::Off => Box::new(render::NullHighligher),
+            Highlighting::On { theme, .. } => Box::new(render::SyntectHighlighter::new(
+                theme,
+                render::HighlightStyle::Inline,
+            )?),
+        })
+    }
+
+    pub fn highlighter(&self) -> crate::Result<Box<dyn render::CodeHighlighter>> {
+        Ok(match &self.highlighting {
+            Highlighting::Off => Box::new(render::NullHighligher),
+            Highlighting::On { theme, style } => {
+                Box::new(render::SyntectHighlighter::new(theme, style.clone())?)
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct Output {
     pub(crate) output: files::Path,
     pub(crate) clean: bool,
 }
 
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Serve {
+    pub(crate) listen: String,
+    // what links point at while serving, when the address browsers reach the server on isn't
+    // the one it binds to
+    pub(crate) public_url: Option<String>,
+    pub(crate) reload: bool,
+    pub(crate) also_watch: Vec<path::PathBuf>,
+}
+
+impl Default for Serve {
+    fn default() -> Self {
+        Self {
+            listen: "localhost:1312".to_owned(),
+            public_url: None,
+            reload: true,
+            also_watch: Default::default(),
+        }
+    }
+}
+
+// url patterns keyed by what they're for, e.g. pages: "pages/{slug}"
+#[derive(Clone, Debug, Default)]
+pub struct Formats(BTreeMap<String, String>);
+
+impl Formats {
+    pub fn get<S: AsRef<str>>(&self, key: S) -> Option<&str> {
+        self.0.get(key.as_ref()).map(|f| f.as_str())
+    }
+
+    // the directory portion of a pattern that ends with {slug}
+    pub fn root<S: AsRef<str>>(&self, key: S) -> Option<files::DirPath> {
+        self.get(key)
+            .and_then(|fmt| fmt.strip_suffix("{slug}"))
+            .map(|root| root.trim_end_matches('/'))
+            .filter(|root| !root.is_empty())
+            .map(|root| unsafe { files::DirPath::new(root) })
+    }
+}
+
+// a frontmatter key whose values group pages, every term gets a page and the
+// taxonomy gets an index of its terms
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Taxonomy {
+    pub(crate) name: String,
+    pub(crate) key: String,
+    pub(crate) title: String,
+    // where each term's page goes, e.g. "categories/{slug}"
+    pub(crate) pattern: String,
+    // where the index of terms goes, e.g. "categories/index.html"
+    pub(crate) index_url: String,
+    pub(crate) index_template: String,
+    pub(crate) term_template: String,
+    pub(crate) ordering: processors::TermOrdering,
+    pub(crate) sorting: processors::Sorting,
+}
+
+impl Taxonomy {
+    // what every site gets unless it declares its own tags
+    pub fn tags(indexer: &Indexer, formats: &Formats) -> Self {
+        Self {
+            name: "tags".to_owned(),
+            key: "tags".to_owned(),
+            title: "Tag Archive".to_owned(),
+            pattern: formats.get("tags").unwrap_or("tags/{slug}").to_owned(),
+            index_url: "tags.html".to_owned(),
+            index_template: "tags.html".to_owned(),
+            term_template: "tag.html".to_owned(),
+            ordering: indexer.tag_ordering,
+            sorting: indexer.tag_sorting,
+        }
+    }
+}
+
+// the frontmatter key the tags taxonomy reads, which a site can change
+pub fn tag_key(taxonomies: &[Taxonomy]) -> &str {
+    taxonomies
+        .iter()
+        .find(|t| t.name == "tags")
+        .map(|t| t.key.as_str())
+        .unwrap_or("tags")
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub enum TransformerSelection {
+    // whatever is turned on by default, plus anything explicitly included
+    Default,
+    All,
+    Only(Vec<String>),
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Transformers {
+    pub(crate) selection: TransformerSelection,
+    pub(crate) include: Vec<String>,
+    pub(crate) configuration: BTreeMap<String, content::Metadata>,
+}
+
+impl Default for Transformers {
+    fn default() -> Self {
+        Self {
+            selection: TransformerSelection::Default,
+            include: Default::default(),
+            configuration: Default::default(),
+        }
+    }
+}
+
+impl Transformers {
+    pub fn enabled<S: AsRef<str>>(&self, name: S, default: bool) -> bool {
+        let name = name.as_ref();
+        let included = self.include.iter().any(|i| i == name);
+        match &self.selection {
+            TransformerSelection::All => true,
+            TransformerSelection::Default => default || included,
+            TransformerSelection::Only(names) => included || names.iter().any(|n| n == name),
+        }
+    }
+
+    pub fn configuration<S: AsRef<str>>(&self, name: S) -> Option<&content::Metadata> {
+        self.configuration.get(name.as_ref())
+    }
+
+    // the settings under `transformers.configuration.<name>`, empty when there are none
+    pub fn options<'a>(&'a self, name: &'a str) -> Options<'a> {
+        Options {
+            name,
+            with: match self.configuration(name) {
+                Some(content::Metadata::Map(m)) => Some(m),
+                _ => None,
+            },
+        }
+    }
+
+    // how archives group and order pages
+    pub fn indexer(&self) -> crate::Result<Indexer> {
+        let mut indexer = Indexer::default();
+        let with = self.options("indexer");
+
+        indexer.date_grouping = match with.str("date-grouping")? {
+            None => indexer.date_grouping,
+            Some("year" | "%Y") => processors::DateGrouping::Year,
+            Some("month" | "year-month" | "%Y-%m") => processors::DateGrouping::Month,
+            Some("day" | "%Y-%m-%d") => processors::DateGrouping::Day,
+            Some(other) => Err(with.invalid("date-grouping", "year, month or day", other))?,
+        };
+        if let Some(sorting) = with.str("date-sorting")? {
+            indexer.date_sorting = sorting_from(sorting, &with.key("date-sorting"))?;
+        }
+        if let Some(ordering) = with.str("tag-ordering")? {
+            indexer.tag_ordering = ordering_from(ordering, &with.key("tag-ordering"))?;
+        }
+        if let Some(sorting) = with.str("tag-sorting")? {
+            indexer.tag_sorting = sorting_from(sorting, &with.key("tag-sorting"))?;
+        }
+        indexer.per_page = self.per_page("indexer")?;
+        Ok(indexer)
+    }
+
+    pub fn sitemap(&self) -> crate::Result<Sitemap> {
+        let mut sitemap = Sitemap::default();
+        let with = self.options("sitemap");
+
+        sitemap.robots = match with.get("robots") {
+            None | Some(content::Metadata::Bool(true)) => Robots::Default,
+            Some(content::Metadata::Bool(false)) => Robots::Off,
+            Some(content::Metadata::Str(s)) => Robots::Custom(s.clone()),
+            Some(other) => {
+                Err(with.invalid("robots", "true, false or the file's contents", other))?
+            }
+        };
+        if let Some(paths) = with.strings("disallow")? {
+            sitemap.disallow = paths.into_iter().map(str::to_owned).collect();
+        }
+        Ok(sitemap)
+    }
+
+    // what atom, rss and json-feed share
+    pub fn feeds(&self) -> crate::Result<Feeds> {
+        let mut feeds = Feeds::default();
+        let with = self.options("feeds");
+
+        if let Some(title) = with.str("title")? {
+            feeds.title = Some(title.to_owned());
+        }
+        if let Some(author) = with.str("author")? {
+            feeds.author = Some(author.to_owned());
+        }
+        feeds.content = match with.str("content")? {
+            None => feeds.content,
+            Some("full") => processors::FeedContent::Full,
+            Some("summary") => processors::FeedContent::Summary,
+            Some(other) => Err(with.invalid("content", "full or summary", other))?,
+        };
+        if let Some(limit) = with.whole("limit", 1..=usize::MAX)? {
+            feeds.limit = limit;
+        }
+        if let Some(terms) = with.bool("terms")? {
+            feeds.terms = terms;
+        }
+        Ok(feeds)
+    }
+
+    // how many pages a listing shows before spilling onto the next, unset lists everything
+    pub fn per_page(&self, name: &str) -> crate::Result<Option<usize>> {
+        self.options(name).whole("per-page", 1..=usize::MAX)
+    }
+
+    // the deepest heading level the table of contents goes down to
+    pub fn toc_depth(&self) -> crate::Result<u8> {
+        Ok(self.options("toc").whole("depth", 1..=6)?.unwrap_or(3) as u8)
+    }
+}
+
+// typed access to one transformer's settings, a setting of the wrong type is an error naming it
+pub struct Options<'a> {
+    name: &'a str,
+    with: Option<&'a BTreeMap<String, content::Metadata>>,
+}
+
+impl<'a> Options<'a> {
+    pub fn get(&self, key: &str) -> Option<&'a content::Metadata> {
+        self.with.and_then(|with| with.get(key))
+    }
+
+    pub fn str(&self, key: &str) -> crate::Result<Option<&'a str>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(content::Metadata::Str(s)) => Ok(Some(s)),
+            Some(other) => Err(self.invalid(key, "a string", other))?,
+        }
+    }
+
+    pub fn bool(&self, key: &str) -> crate::Result<Option<bool>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(content::Metadata::Bool(b)) => Ok(Some(*b)),
+            Some(other) => Err(self.invalid(key, "true or false", other))?,
+        }
+    }
+
+    pub fn whole(
+        &self,
+        key: &str,
+        range: std::ops::RangeInclusive<usize>,
+    ) -> crate::Result<Option<usize>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(content::Metadata::Number(n))
+                if n.fract() == 0.0 && *n >= *range.start() as f64 && *n <= *range.end() as f64 =>
+            {
+                Ok(Some(*n as usize))
+            }
+            Some(other) => {
+                let expected = match (*range.start(), *range.end()) {
+                    (1, usize::MAX) => "a positive whole number".to_owned(),
+                    (from, to) => format!("a whole number from {} to {}", from, to),
+                };
+                Err(self.invalid(key, &expected, other))?
+            }
+        }
+    }
+
+    pub fn strings(&self, key: &str) -> crate::Result<Option<Vec<&'a str>>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(content::Metadata::List(items)) => items
+                .iter()
+                .map(|item| match item {
+                    content::Metadata::Str(s) => Ok(s.as_str()),
+                    other => Err(self.invalid(key, "a list of strings", other).into()),
+                })
+                .collect::<crate::Result<_>>()
+                .map(Some),
+            Some(other) => Err(self.invalid(key, "a list of strings", other))?,
+        }
+    }
+
+    // the setting's full path in the configuration file
+    fn key(&self, key: &str) -> String {
+        format!("transformers.configuration.{}.{}", self.name, key)
+    }
+
+    fn invalid<D: std::fmt::Debug>(&self, key: &str, expected: &str, got: D) -> Error {
+        Error::Invalid(
+            self.key(key),
+            format!("expected {}, got {:?}", expected, got),
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Feeds {
+    // defaults to the site's host
+    pub(crate) title: Option<String>,
+    pub(crate) author: Option<String>,
+    pub(crate) content: processors::FeedContent,
+    // how many of the newest articles each feed carries
+    pub(crate) limit: usize,
+    // every taxonomy term also gets feeds next to its page
+    pub(crate) terms: bool,
+}
+
+impl Default for Feeds {
+    fn default() -> Self {
+        Self {
+            title: None,
+            author: None,
+            content: Default::default(),
+            limit: 20,
+            terms: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Sitemap {
+    pub(crate) robots: Robots,
+    // paths the default robots.txt asks crawlers to stay out of
+    pub(crate) disallow: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub enum Robots {
+    // no robots.txt is written, e.g. when one is included with the static files
+    Off,
+    // lets every crawler in, less anything disallowed
+    #[default]
+    Default,
+    // written as is, the sitemap line is added after it
+    Custom(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Indexer {
+    pub(crate) date_grouping: processors::DateGrouping,
+    pub(crate) date_sorting: processors::Sorting,
+    pub(crate) tag_ordering: processors::TermOrdering,
+    pub(crate) tag_sorting: processors::Sorting,
+    // archive pages are paginated when set
+    pub(crate) per_page: Option<usize>,
+}
+
+impl Default for Indexer {
+    fn default() -> Self {
+        Self {
+            date_grouping: Default::default(),
+            // most recent first
+            date_sorting: processors::Sorting::Descending,
+            tag_ordering: Default::default(),
+            tag_sorting: processors::Sorting::Ascending,
+            per_page: None,
+        }
+    }
+}
+
+fn sorting_from(s: &str, key: &str) -> Result<processors::Sorting, Error> {
+    match s {
+        "asc" | "ascending" => Ok(processors::Sorting::Ascending),
+        "desc" | "descending" => Ok(processors::Sorting::Descending),
+        other => Err(Error::Invalid(
+            key.to_owned(),
+            format!("expected asc or desc, got {}", other),
+        )),
+    }
+}
+
+fn ordering_from(s: &str, key: &str) -> Result<processors::TermOrdering, Error> {
+    match s {
+        "alphabetical" => Ok(processors::TermOrdering::Alphabetical),
+        "count" => Ok(processors::TermOrdering::Count),
+        "recent" | "most-recent" => Ok(processors::TermOrdering::Recent),
+        other => Err(Error::Invalid(
+            key.to_owned(),
+            format!("expected alphabetical, count or recent, got {}", other),
+        )),
+    }
+}
+
+impl Configuration {
+    // everything that feeds into a build
+    pub fn watched(&self) -> Vec<path::PathBuf> {
+        let mut paths = vec![
+            AsRef::<path::Path>::as_ref(&self.content.base).to_owned(),
+            AsRef::<path::Path>::as_ref(&self.site.templates).to_owned(),
+        ];
+        paths.extend(
+            self.content
+                .include
+                .iter()
+                .map(|i| AsRef::<path::Path>::as_ref(&i.path).to_owned()),
+        );
+        paths.extend(self.serve.also_watch.iter().cloned());
+        paths
+    }
+}
+
+impl Serve {
+    // the base for links while serving, a server bound to every interface is still reached
+    // through localhost
+    pub fn base_url(&self) -> crate::Result<Url> {
+        if let Some(url) = &self.public_url {
+            return parse_base_url(url, false);
+        }
+        let (host, port) = match self.listen.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (self.listen.as_str(), None),
+        };
+        let host = match host {
+            "" | "0.0.0.0" | "[::]" | "::" => "localhost",
+            host => host,
+        };
+        match port {
+            Some(port) => parse_base_url(format!("{}:{}", host, port), false),
+            None => parse_base_url(host, false),
+        }
+    }
+}
+
 impl Output {
     pub fn writer(&self) -> crate::Result<Box<dyn site::Writer>> {
         match &self.output {
@@ -52,3 +559,666 @@ impl Output {
         }
     }
 }
+
+// treats anything that looks like an archive as a file, everything else as a directory
+pub fn output_path<P: Into<path::PathBuf>>(p: P) -> files::Path {
+    let p = p.into();
+    match p.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") | Some("tar") => files::Path::File(unsafe { files::FilePath::new(p) }),
+        _ => files::Path::Dir(unsafe { files::DirPath::new(p) }),
+    }
+}
+
+pub fn parse_base_url<S: AsRef<str>>(raw: S, https: bool) -> crate::Result<Url> {
+    let raw = raw.as_ref();
+    // "localhost:1312" would otherwise parse as a url with a "localhost" scheme
+    let parsed = if raw.contains("://") {
+        Url::parse(raw)
+    } else {
+        let scheme = if https { "https" } else { "http" };
+        Url::parse(&format!("{scheme}://{raw}"))
+    };
+    let mut parsed = parsed.map_err(|e| {
+        Box::new(Error::Invalid("site.baseUrl".to_owned(), e.to_string()))
+            as Box<dyn std::error::Error + Send + Sync>
+    })?;
+    // urls are joined onto the base, without the slash "example.com/blog" would lose "/blog"
+    if !parsed.path().ends_with('/') {
+        parsed.set_path(&format!("{}/", parsed.path()));
+    }
+    Ok(parsed)
+}
+
+impl Configuration {
+    // paths in the file are relative to the directory the file lives in
+    pub fn load<P: AsRef<path::Path>>(path: P) -> crate::Result<Configuration> {
+        let path = path.as_ref();
+        let raw = fs::read_to_string(path)?;
+        let docs = YamlLoader::load_from_str(&raw)
+            .map_err(|e| Error::Invalid(path.to_string_lossy().into_owned(), e.to_string()))?;
+        let root = match docs.first() {
+            Some(doc @ Yaml::Hash(_)) => doc,
+            _ => {
+                return Err(Box::new(Error::Invalid(
+                    path.to_string_lossy().into_owned(),
+                    "expected a mapping at the top level".to_owned(),
+                )))
+            }
+        };
+        let relative_to = path.parent().unwrap_or(path::Path::new("")).to_owned();
+        ConfigReader { relative_to }.read(root)
+    }
+}
+
+struct ConfigReader {
+    relative_to: path::PathBuf,
+}
+
+impl ConfigReader {
+    fn read(&self, root: &Yaml) -> crate::Result<Configuration> {
+        let site = &root["site"];
+        let content = &root["content"];
+        let output = &root["output"];
+
+        let https = !matches!(string(&site["https"]), Some("no") | Some("never"))
+            && !matches!(site["https"], Yaml::Boolean(false));
+        let base_url = parse_base_url(required(string(&site["baseUrl"]), "site.baseUrl")?, https)?;
+
+        let formats = Formats(match &output["formats"] {
+            Yaml::Hash(h) => h
+                .iter()
+                .filter_map(|(k, v)| Some((k.as_str()?.to_owned(), v.as_str()?.to_owned())))
+                .collect(),
+            _ => Default::default(),
+        });
+
+        let transformers = self.transformers(&root["transformers"])?;
+        let taxonomies =
+            self.taxonomies(&root["taxonomies"], &transformers.indexer()?, &formats)?;
+
+        Ok(Configuration {
+            content: Content {
+                base: unsafe {
+                    files::DirPath::new(self.path(required(
+                        string(&content["pages"]["path"]),
+                        "content.pages.path",
+                    )?))
+                },
+                include: list(&content["include"])
+                    .map(|incl| {
+                        let path = required(string(&incl["path"]), "content.include.path")?;
+                        Ok(Include {
+                            path: self.existing(path)?,
+                            as_: string(&incl["as"]).map(|a| a.to_owned()),
+                        })
+                    })
+                    .collect::<crate::Result<_>>()?,
+                exclude: list(&content["exclude"])
+                    .filter_map(|ex| string(ex).map(|ex| self.glob(ex)))
+                    .collect(),
+                include_hidden: boolean(&content["includeHidden"]).unwrap_or(false),
+                pages: list(&content["pages"]["include"])
+                    .filter_map(|inc| string(inc).map(|inc| self.glob(inc)))
+                    .collect(),
+                preserve_structure: boolean(&content["pages"]["perserveStructureOnOutput"])
+                    .or(boolean(&content["pages"]["preserveStructureOnOutput"]))
+                    .unwrap_or(false),
+                raw_html: match string(&content["pages"]["rawHtml"]) {
+                    None | Some("passthrough") => md::HtmlPolicy::Passthrough,
+                    Some("sanitize") => md::HtmlPolicy::Sanitize,
+                    Some("reject") => md::HtmlPolicy::Reject,
+                    Some(other) => {
+                        return Err(Box::new(Error::Invalid(
+                            "content.pages.rawHtml".to_owned(),
+                            format!("expected passthrough, sanitize or reject, got {}", other),
+                        )))
+                    }
+                },
+            },
+            site: Site {
+                templates: unsafe {
+                    files::DirPath::new(self.path(required(
+                        string(&site["template"]["path"]),
+                        "site.template.path",
+                    )?))
+                },
+                base_url,
+            },
+            rendering: Rendering {
+                slug_style: if boolean(&output["writeAsDirectories"]).unwrap_or(false) {
+                    site::ArticleSlugStyle::Directory
+                } else {
+                    site::ArticleSlugStyle::Page
+                },
+                page_root: formats.root("pages"),
+                highlighting: self.highlighting(&output["highlighting"])?,
+                heading_anchors: boolean(&output["headingAnchors"]).unwrap_or(false),
+                localization: self.localization(&output["localization"])?,
+            },
+            output: Output {
+                output: {
+                    let out = self.path(string(&output["files"]["path"]).unwrap_or("public"));
+                    if boolean(&output["files"]["archive"]).unwrap_or(false) {
+                        files::Path::File(unsafe { files::FilePath::new(out) })
+                    } else {
+                        output_path(out)
+                    }
+                },
+                clean: boolean(&output["clean"]).unwrap_or(true),
+            },
+            transformers,
+            taxonomies,
+            cache: match boolean(&root["cache"]["enabled"]) {
+                Some(false) => None,
+                _ => Some(self.path(string(&root["cache"]["path"]).unwrap_or(DEFAULT_CACHE))),
+            },
+            serve: {
+                let defaults = Serve::default();
+                Serve {
+                    listen: string(&output["serve"]["listen"])
+                        .map(|l| l.to_owned())
+                        .unwrap_or(defaults.listen),
+                    public_url: string(&output["serve"]["publicUrl"]).map(|u| u.to_owned()),
+                    reload: boolean(&output["serve"]["reload"]).unwrap_or(defaults.reload),
+                    also_watch: list(&output["serve"]["alsoWatch"])
+                        .filter_map(|p| string(p).map(|p| self.path(p)))
+                        .collect(),
+                }
+            },
+        })
+    }
+
+    fn localization(&self, l: &Yaml) -> crate::Result<Localization> {
+        let mut localization = Localization::default();
+        if let Some(pattern) = string(&l["datePattern"]) {
+            content::date::check_pattern(pattern).map_err(|e| {
+                Error::Invalid("output.localization.datePattern".to_owned(), e.to_string())
+            })?;
+            localization.date_pattern = pattern.to_owned();
+        }
+        if let Some(tz) = string(&l["timezone"]) {
+            localization.timezone = tz.parse().map_err(|_| {
+                Error::Invalid(
+                    "output.localization.timezone".to_owned(),
+                    format!("unknown timezone {}", tz),
+                )
+            })?;
+        }
+        Ok(localization)
+    }
+
+    fn highlighting(&self, h: &Yaml) -> crate::Result<Highlighting> {
+        let theme = string(&h["theme"]).unwrap_or("InspiredGitHub").to_owned();
+        let style = match string(&h["style"]) {
+            None | Some("none") => return Ok(Highlighting::Off),
+            Some("inline") => render::HighlightStyle::Inline,
+            Some("classes") => render::HighlightStyle::Classes {
+                stylesheet: unsafe {
+                    files::FilePath::new(string(&h["stylesheet"]).unwrap_or("highlight.css"))
+                },
+            },
+            Some(other) => {
+                return Err(Box::new(Error::Invalid(
+                    "output.highlighting.style".to_owned(),
+                    format!("expected classes, inline or none, got {}", other),
+                )))
+            }
+        };
+        Ok(Highlighting::On { theme, style })
+    }
+
+    fn transformers(&self, t: &Yaml) -> crate::Result<Transformers> {
+        let selection = match &t["use"] {
+            Yaml::Null | Yaml::BadValue => TransformerSelection::Default,
+            Yaml::String(s) if s == "default" => TransformerSelection::Default,
+            Yaml::String(s) if s == "*" => TransformerSelection::All,
+            Yaml::Array(names) => TransformerSelection::Only(
+                names
+                    .iter()
+                    .filter_map(|n| n.as_str().map(|n| n.to_owned()))
+                    .collect(),
+            ),
+            other => {
+                return Err(Box::new(Error::Invalid(
+                    "transformers.use".to_owned(),
+                    format!("unsupported value {:?}", other),
+                )))
+            }
+        };
+
+        let mut configuration = BTreeMap::new();
+        for conf in list(&t["configuration"]) {
+            let name = required(string(&conf["name"]), "transformers.configuration.name")?;
+            let with = match &conf["with"] {
+                Yaml::BadValue | Yaml::Null => content::Metadata::Map(Default::default()),
+                with => content::Metadata::try_from(with)?,
+            };
+            configuration.insert(name.to_owned(), with);
+        }
+
+        Ok(Transformers {
+            selection,
+            include: list(&t["include"])
+                .filter_map(|i| string(i).map(|i| i.to_owned()))
+                .collect(),
+            configuration,
+        })
+    }
+
+    // tags are always a taxonomy, declaring one named tags replaces the default
+    fn taxonomies(
+        &self,
+        t: &Yaml,
+        indexer: &Indexer,
+        formats: &Formats,
+    ) -> crate::Result<Vec<Taxonomy>> {
+        let mut taxonomies = vec![Taxonomy::tags(indexer, formats)];
+        for tax in list(t) {
+            let name = required(string(&tax["name"]), "taxonomies.name")?;
+            let key = |k: &str| format!("taxonomies.{}.{}", name, k);
+            let mut taxonomy = taxonomies
+                .iter()
+                .find(|t| t.name == name)
+                .cloned()
+                .unwrap_or_else(|| Taxonomy {
+                    name: name.to_owned(),
+                    key: name.to_owned(),
+                    title: name.to_owned(),
+                    pattern: formats
+                        .get(name)
+                        .map(|p| p.to_owned())
+                        .unwrap_or_else(|| format!("{}/{{slug}}", name)),
+                    index_url: format!("{}.html", name),
+                    index_template: format!("{}.html", name),
+                    term_template: format!("{}_term.html", name),
+                    ordering: Default::default(),
+                    sorting: Default::default(),
+                });
+
+            if let Some(k) = string(&tax["key"]) {
+                taxonomy.key = k.to_owned();
+            }
+            if let Some(title) = string(&tax["title"]) {
+                taxonomy.title = title.to_owned();
+            }
+            if let Some(pattern) = string(&tax["path"]) {
+                taxonomy.pattern = pattern.to_owned();
+            }
+            if !taxonomy.pattern.contains("{slug}") {
+                return Err(Box::new(Error::Invalid(
+                    key("path"),
+                    format!(
+                        "expected a pattern containing {{slug}}, got {}",
+                        taxonomy.pattern
+                    ),
+                )));
+            }
+            if let Some(index) = string(&tax["indexPath"]) {
+                // a directory gets an index.html, anything else is the file itself
+                let index = index.trim_matches('/');
+                if index.is_empty() || index.contains("{slug}") {
+                    return Err(Box::new(Error::Invalid(
+                        key("indexPath"),
+                        format!("expected a path without {{slug}}, got {}", index),
+                    )));
+                }
+                taxonomy.index_url = if index.ends_with(".html") {
+                    index.to_owned()
+                } else {
+                    format!("{}/index.html", index)
+                };
+            }
+            if let Some(template) = string(&tax["indexTemplate"]) {
+                taxonomy.index_template = template.to_owned();
+            }
+            if let Some(template) = string(&tax["template"]) {
+                taxonomy.term_template = template.to_owned();
+            }
+            if let Some(ordering) = string(&tax["ordering"]) {
+                taxonomy.ordering = ordering_from(ordering, &key("ordering"))?;
+            }
+            if let Some(sorting) = string(&tax["sorting"]) {
+                taxonomy.sorting = sorting_from(sorting, &key("sorting"))?;
+            }
+
+            taxonomies.retain(|t| t.name != name);
+            taxonomies.push(taxonomy);
+        }
+        Ok(taxonomies)
+    }
+
+    fn path<P: AsRef<path::Path>>(&self, p: P) -> path::PathBuf {
+        self.relative_to.join(p)
+    }
+
+    fn glob(&self, pattern: &str) -> String {
+        self.path(pattern).to_string_lossy().into_owned()
+    }
+
+    fn existing<P: AsRef<path::Path>>(&self, p: P) -> crate::Result<files::Path> {
+        files::Path::parse(self.path(p)).map_err(|p| {
+            Box::new(files::PathError::Unsupported(p)) as Box<dyn std::error::Error + Send + Sync>
+        })
+    }
+}
+
+fn string(y: &Yaml) -> Option<&str> {
+    y.as_str()
+}
+
+fn boolean(y: &Yaml) -> Option<bool> {
+    y.as_bool()
+}
+
+fn list(y: &Yaml) -> impl Iterator<Item = &Yaml> {
+    y.as_vec().into_iter().flatten()
+}
+
+fn required<T>(value: Option<T>, key: &str) -> crate::Result<T> {
+    value.ok_or_else(|| {
+        Box::new(Error::Missing(key.to_owned())) as Box<dyn std::error::Error + Send + Sync>
+    })
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Missing(String),
+    Invalid(String, String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Error::*;
+        write!(f, "ConfigError::")?;
+        match self {
+            Missing(key) => write!(f, "Missing({})", key),
+            Invalid(key, reason) => write!(f, "Invalid({}: {})", key, reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(yaml: &str) -> crate::Result<Configuration> {
+        let docs = YamlLoader::load_from_str(yaml).unwrap();
+        ConfigReader {
+            relative_to: "site".into(),
+        }
+        .read(&docs[0])
+    }
+
+    fn minimal(rest: &str) -> String {
+        format!(
+            "site:\n  baseUrl: example.com/blog\n  template:\n    path: templates\n\
+             content:\n  pages:\n    path: content\n{}",
+            rest
+        )
+    }
+
+    fn error(yaml: &str) -> String {
+        read(yaml).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn fills_in_defaults() {
+        let conf = read(&minimal("")).unwrap();
+        assert_eq!(conf.site.base_url.as_str(), "https://example.com/blog/");
+        assert_eq!(
+            AsRef::<path::Path>::as_ref(&conf.content.base),
+            path::Path::new("site/content")
+        );
+        assert_eq!(
+            AsRef::<path::Path>::as_ref(&conf.output.output),
+            path::Path::new("site/public")
+        );
+        assert_eq!(
+            conf.cache,
+            Some(path::Path::new("site").join(DEFAULT_CACHE))
+        );
+        assert!(conf.output.clean);
+        assert!(matches!(conf.rendering.highlighting, Highlighting::Off));
+        assert!(matches!(conf.content.raw_html, md::HtmlPolicy::Passthrough));
+        assert_eq!(conf.rendering.localization.timezone, chrono_tz::UTC);
+        assert_eq!(
+            conf.taxonomies
+                .iter()
+                .map(|t| t.name.as_str())
+                .collect::<Vec<_>>(),
+            ["tags"]
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_invalid_keys() {
+        assert_eq!(
+            error("site:\n  template:\n    path: t\n"),
+            "ConfigError::Missing(site.baseUrl)"
+        );
+        assert_eq!(
+            error("site:\n  baseUrl: example.com\ncontent:\n  pages:\n    path: c\n"),
+            "ConfigError::Missing(site.template.path)"
+        );
+        assert_eq!(
+            error(&minimal("output:\n  highlighting:\n    style: fancy\n")),
+            "ConfigError::Invalid(output.highlighting.style: expected classes, inline or none, got fancy)"
+        );
+        assert_eq!(
+            error(&minimal(
+                "output:\n  localization:\n    timezone: Mars/Olympus\n"
+            )),
+            "ConfigError::Invalid(output.localization.timezone: unknown timezone Mars/Olympus)"
+        );
+        assert!(error(&minimal(
+            "output:\n  localization:\n    datePattern: \"%Q\"\n"
+        ))
+        .starts_with("ConfigError::Invalid(output.localization.datePattern"));
+        let raw_html =
+            minimal("").replace("path: content\n", "path: content\n    rawHtml: maybe\n");
+        assert!(error(&raw_html).starts_with("ConfigError::Invalid(content.pages.rawHtml"));
+    }
+
+    #[test]
+    fn base_url_scheme_and_trailing_slash() {
+        let conf = read(&minimal("").replace("site:\n", "site:\n  https: never\n")).unwrap();
+        assert_eq!(conf.site.base_url.as_str(), "http://example.com/blog/");
+
+        assert_eq!(
+            parse_base_url("http://localhost:1312", true)
+                .unwrap()
+                .as_str(),
+            "http://localhost:1312/"
+        );
+        assert_eq!(
+            parse_base_url("localhost:1312", false).unwrap().as_str(),
+            "http://localhost:1312/"
+        );
+        assert!(parse_base_url("https://", true).is_err());
+    }
+
+    #[test]
+    fn serving_on_every_interface_links_to_localhost() {
+        let mut serve = Serve {
+            listen: "0.0.0.0:8080".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(serve.base_url().unwrap().as_str(), "http://localhost:8080/");
+        serve.listen = "127.0.0.1:3000".to_owned();
+        assert_eq!(serve.base_url().unwrap().as_str(), "http://127.0.0.1:3000/");
+        serve.public_url = Some("https://preview.example.com/site".to_owned());
+        assert_eq!(
+            serve.base_url().unwrap().as_str(),
+            "https://preview.example.com/site/"
+        );
+    }
+
+    #[test]
+    fn archives_are_written_as_files() {
+        assert!(matches!(output_path("out.tar.gz"), files::Path::File(_)));
+        assert!(matches!(output_path("out.tar"), files::Path::File(_)));
+        assert!(matches!(output_path("public"), files::Path::Dir(_)));
+    }
+
+    #[test]
+    fn selects_transformers() {
+        let conf = read(&minimal("transformers:\n  include: [atom]\n")).unwrap();
+        assert!(conf.transformers.enabled("toc", true));
+        assert!(conf.transformers.enabled("atom", false));
+        assert!(!conf.transformers.enabled("rss", false));
+
+        let conf = read(&minimal("transformers:\n  use: [rss]\n")).unwrap();
+        assert!(!conf.transformers.enabled("toc", true));
+        assert!(conf.transformers.enabled("rss", false));
+
+        let conf = read(&minimal("transformers:\n  use: \"*\"\n")).unwrap();
+        assert!(conf.transformers.enabled("sitemap", false));
+
+        assert!(error(&minimal("transformers:\n  use: 3\n"))
+            .starts_with("ConfigError::Invalid(transformers.use"));
+    }
+
+    fn options(name: &str, with: &str) -> Transformers {
+        let conf = read(&minimal(&format!(
+            "transformers:\n  configuration:\n    - name: {}\n      with:\n{}",
+            name, with
+        )))
+        .unwrap();
+        conf.transformers
+    }
+
+    #[test]
+    fn reads_transformer_options() {
+        let indexer = options(
+            "indexer",
+            "        date-grouping: year\n        tag-ordering: count\n        per-page: 5\n",
+        )
+        .indexer()
+        .unwrap();
+        assert!(matches!(
+            indexer.date_grouping,
+            processors::DateGrouping::Year
+        ));
+        assert!(matches!(
+            indexer.tag_ordering,
+            processors::TermOrdering::Count
+        ));
+        assert_eq!(indexer.per_page, Some(5));
+
+        let feeds = options(
+            "feeds",
+            "        title: Notes\n        content: full\n        limit: 3\n        terms: false\n",
+        )
+        .feeds()
+        .unwrap();
+        assert_eq!(feeds.title.as_deref(), Some("Notes"));
+        assert!(matches!(feeds.content, processors::FeedContent::Full));
+        assert_eq!(feeds.limit, 3);
+        assert!(!feeds.terms);
+
+        let sitemap = options(
+            "sitemap",
+            "        robots: false\n        disallow: [/drafts/, /private/]\n",
+        )
+        .sitemap()
+        .unwrap();
+        assert!(matches!(sitemap.robots, Robots::Off));
+        assert_eq!(sitemap.disallow, ["/drafts/", "/private/"]);
+
+        // nothing configured is everything at its default
+        let transformers = Transformers::default();
+        assert_eq!(transformers.feeds().unwrap().limit, 20);
+        assert_eq!(transformers.per_page("index").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_options_of_the_wrong_type() {
+        // the indexer is already read for the taxonomies while loading
+        let error = |name: &str, with: &str| {
+            read(&minimal(&format!(
+                "transformers:\n  configuration:\n    - name: {}\n      with:\n{}",
+                name, with
+            )))
+            .and_then(|conf| match name {
+                "feeds" => conf.transformers.feeds().map(|_| ()),
+                "sitemap" => conf.transformers.sitemap().map(|_| ()),
+                _ => Ok(()),
+            })
+            .unwrap_err()
+            .to_string()
+        };
+        assert_eq!(
+            error("indexer", "        date-grouping: week\n"),
+            "ConfigError::Invalid(transformers.configuration.indexer.date-grouping: \
+             expected year, month or day, got \"week\")"
+        );
+        assert_eq!(
+            error("indexer", "        per-page: 0\n"),
+            "ConfigError::Invalid(transformers.configuration.indexer.per-page: \
+             expected a positive whole number, got Number(0.0))"
+        );
+        assert_eq!(
+            error("feeds", "        limit: 2.5\n"),
+            "ConfigError::Invalid(transformers.configuration.feeds.limit: \
+             expected a positive whole number, got Number(2.5))"
+        );
+        assert_eq!(
+            error("feeds", "        terms: yes please\n"),
+            "ConfigError::Invalid(transformers.configuration.feeds.terms: \
+             expected true or false, got Str(\"yes please\"))"
+        );
+        assert_eq!(
+            error("sitemap", "        disallow: [/a/, 3]\n"),
+            "ConfigError::Invalid(transformers.configuration.sitemap.disallow: \
+             expected a list of strings, got Number(3.0))"
+        );
+    }
+
+    #[test]
+    fn toc_depth_is_a_heading_level() {
+        assert_eq!(Transformers::default().toc_depth().unwrap(), 3);
+        for depth in 1..=6 {
+            let with = format!("        depth: {}\n", depth);
+            assert_eq!(options("toc", &with).toc_depth().unwrap(), depth);
+        }
+        for depth in ["0", "7", "2.5", "-1", "deep"] {
+            let with = format!("        depth: {}\n", depth);
+            assert!(
+                options("toc", &with)
+                    .toc_depth()
+                    .unwrap_err()
+                    .to_string()
+                    .starts_with("ConfigError::Invalid(transformers.configuration.toc.depth: expected a whole number from 1 to 6"),
+                "{}",
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn declares_taxonomies() {
+        let conf = read(&minimal(
+            "taxonomies:\n  - name: tags\n    key: keywords\n\
+             \x20 - name: series\n    indexPath: series/\n    ordering: count\n",
+        ))
+        .unwrap();
+        assert_eq!(tag_key(&conf.taxonomies), "keywords");
+        let series = conf.taxonomies.iter().find(|t| t.name == "series").unwrap();
+        assert_eq!(series.key, "series");
+        assert_eq!(series.pattern, "series/{slug}");
+        assert_eq!(series.index_url, "series/index.html");
+        assert_eq!(series.term_template, "series_term.html");
+        assert!(matches!(series.ordering, processors::TermOrdering::Count));
+
+        assert!(error(&minimal(
+            "taxonomies:\n  - name: series\n    path: series/all\n"
+        ))
+        .starts_with("ConfigError::Invalid(taxonomies.series.path"));
+        assert!(error(&minimal(
+            "taxonomies:\n  - name: series\n    indexPath: series/{slug}\n"
+        ))
+        .starts_with("ConfigError::Invalid(taxonomies.series.indexPath"));
+    }
+}
@@ -2,7 +2,10 @@ use super::page::Page;
 use super::PageBuilder;
 use crate::files;
 use crate::ids;
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug)]
 pub struct Corpus {
@@ -16,24 +19,27 @@ pub enum CorpusEntry {
     StaticAsset(IncludedPath),
 }
 
+// something copied into the output as is, `dest` is relative to the output's root
 #[derive(Debug, Clone)]
-pub struct IncludedPath(files::Path);
+pub struct IncludedPath {
+    // directories are expanded into their files before they're included
+    src: files::FilePath,
+    dest: PathBuf,
+}
 
-impl From<&IncludedPath> for files::Path {
-    fn from(value: &IncludedPath) -> Self {
-        value.0.clone()
+impl IncludedPath {
+    pub fn source(&self) -> &files::FilePath {
+        &self.src
     }
-}
 
-impl From<files::FilePath> for IncludedPath {
-    fn from(value: files::FilePath) -> Self {
-        Self(value.into())
+    pub fn destination(&self) -> &Path {
+        &self.dest
     }
 }
 
-impl From<files::DirPath> for IncludedPath {
-    fn from(value: files::DirPath) -> Self {
-        Self(value.into())
+impl From<&IncludedPath> for files::Path {
+    fn from(value: &IncludedPath) -> Self {
+        files::Path::File(value.src.clone())
     }
 }
 
@@ -50,16 +56,31 @@ impl Corpus {
         PageBuilder::new(self.ids.next(), f)
     }
 
-    pub fn add_page(&mut self, page: PageBuilder) -> crate::Result<()> {
+    pub fn add_page(&mut self, page: PageBuilder) -> crate::Result<&Page> {
+        let id = page.id.clone();
         self.corpus
-            .insert(page.id.clone(), CorpusEntry::Page(page.build()?));
-        Ok(())
+            .insert(id.clone(), CorpusEntry::Page(page.build()?));
+        Ok(self.page(&id).unwrap())
+    }
+
+    pub fn page(&self, id: &ids::Id<CorpusEntry>) -> Option<&Page> {
+        match self.corpus.get(id) {
+            Some(CorpusEntry::Page(p)) => Some(p),
+            _ => None,
+        }
     }
 
-    pub fn include_asset<P: Into<files::Path>>(&mut self, p: P) -> crate::Result<()> {
+    pub fn include_asset<D: Into<PathBuf>>(
+        &mut self,
+        p: files::FilePath,
+        dest: D,
+    ) -> crate::Result<()> {
         self.corpus.insert(
             self.ids.next(),
-            CorpusEntry::StaticAsset(IncludedPath(p.into())),
+            CorpusEntry::StaticAsset(IncludedPath {
+                src: p,
+                dest: dest.into(),
+            }),
         );
         Ok(())
     }
@@ -86,12 +107,12 @@ impl Corpus {
 
 impl Into<files::Path> for IncludedPath {
     fn into(self) -> files::Path {
-        self.0
+        files::Path::File(self.src)
     }
 }
 
 impl AsRef<Path> for IncludedPath {
     fn as_ref(&self) -> &Path {
-        self.0.as_ref()
+        self.src.as_ref()
     }
 }
@@ -0,0 +1,185 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, TimeZone,
+};
+use chrono_tz::Tz;
+
+// dates and times without an offset are read in the site's timezone
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+// when a page was written, ordered chronologically and serialized as rfc 3339
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub struct Date(DateTime<FixedOffset>);
+
+impl Date {
+    // rfc 3339 (2024-06-01T09:30:00+02:00), a date and time (2024-06-01 09:30) or just a date
+    pub fn parse<S: AsRef<str>>(s: S, tz: Tz) -> Result<Date, DateError> {
+        let s = s.as_ref().trim();
+        if let Ok(d) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Date(d));
+        }
+
+        let naive = DATETIME_FORMATS
+            .iter()
+            .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|d| d.and_hms_opt(0, 0, 0))
+            })
+            .ok_or_else(|| DateError::Invalid(s.to_owned()))?;
+
+        match tz.from_local_datetime(&naive) {
+            LocalResult::Single(d) => Ok(Date(d.fixed_offset())),
+            // the hour repeated when the clocks go back, the first one is as good as any
+            LocalResult::Ambiguous(d, _) => Ok(Date(d.fixed_offset())),
+            LocalResult::None => Err(DateError::Nonexistent(s.to_owned(), tz)),
+        }
+    }
+
+    pub fn now() -> Date {
+        Date(chrono::Utc::now().fixed_offset())
+    }
+
+    pub fn format<S: AsRef<str>>(&self, pattern: S, tz: Tz) -> String {
+        self.0
+            .with_timezone(&tz)
+            .format(pattern.as_ref())
+            .to_string()
+    }
+
+    // what rss wants, e.g. Tue, 2 Jan 2024 09:00:00 -0500
+    pub fn to_rfc2822(self) -> String {
+        self.0.to_rfc2822()
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.to_rfc3339())
+    }
+}
+
+// chrono panics when formatting with a broken pattern so they're checked up front
+pub fn check_pattern<S: AsRef<str>>(pattern: S) -> Result<(), DateError> {
+    let pattern = pattern.as_ref();
+    if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+        Err(DateError::Pattern(pattern.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum DateError {
+    Invalid(String),
+    Nonexistent(String, Tz),
+    Pattern(String),
+}
+
+impl std::fmt::Display for DateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use DateError::*;
+        write!(f, "DateError::")?;
+        match self {
+            Invalid(s) => write!(
+                f,
+                "Invalid({}, expected YYYY-MM-DD, YYYY-MM-DD HH:MM[:SS] or rfc 3339)",
+                s
+            ),
+            Nonexistent(s, tz) => write!(f, "Nonexistent({} in {})", s, tz),
+            Pattern(p) => write!(f, "Pattern({})", p),
+        }
+    }
+}
+
+impl std::error::Error for DateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_pattern, Date};
+    use chrono_tz::{Europe::Berlin, UTC};
+
+    #[test]
+    fn reads_dates_in_the_site_timezone() {
+        assert_eq!(
+            Date::parse("2024-06-01", Berlin).unwrap().to_string(),
+            "2024-06-01T00:00:00+02:00"
+        );
+        assert_eq!(
+            Date::parse("2024-01-15 09:30", Berlin).unwrap().to_string(),
+            "2024-01-15T09:30:00+01:00"
+        );
+        assert_eq!(
+            Date::parse(" 2024-01-15T09:30:15.5 ", UTC)
+                .unwrap()
+                .to_string(),
+            "2024-01-15T09:30:15.500+00:00"
+        );
+        // an explicit offset wins over the site's timezone
+        assert_eq!(
+            Date::parse("2024-06-01T09:30:00-05:00", Berlin)
+                .unwrap()
+                .to_string(),
+            "2024-06-01T09:30:00-05:00"
+        );
+    }
+
+    #[test]
+    fn clock_changes() {
+        // 02:30 never happens when berlin springs forward
+        assert_eq!(
+            Date::parse("2024-03-31 02:30", Berlin)
+                .unwrap_err()
+                .to_string(),
+            "DateError::Nonexistent(2024-03-31 02:30 in Europe/Berlin)"
+        );
+        // and happens twice when it falls back, the first is taken
+        assert_eq!(
+            Date::parse("2024-10-27 02:30", Berlin).unwrap().to_string(),
+            "2024-10-27T02:30:00+02:00"
+        );
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_read() {
+        for bad in ["", "yesterday", "2024-13-01", "01/02/2024", "2024-02-30"] {
+            assert!(Date::parse(bad, UTC).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            Date::parse("soon", UTC).unwrap_err().to_string(),
+            "DateError::Invalid(soon, expected YYYY-MM-DD, YYYY-MM-DD HH:MM[:SS] or rfc 3339)"
+        );
+    }
+
+    #[test]
+    fn orders_instants_across_offsets() {
+        let berlin = Date::parse("2024-06-01 10:00", Berlin).unwrap();
+        let utc = Date::parse("2024-06-01 09:00", UTC).unwrap();
+        assert!(berlin < utc);
+    }
+
+    #[test]
+    fn formats_in_a_timezone() {
+        let d = Date::parse("2024-01-02T23:30:00Z", UTC).unwrap();
+        assert_eq!(d.format("%Y-%m-%d %H:%M", Berlin), "2024-01-03 00:30");
+        assert_eq!(d.format("%Y-%m-%d", UTC), "2024-01-02");
+        assert_eq!(d.to_rfc2822(), "Tue, 2 Jan 2024 23:30:00 +0000");
+    }
+
+    #[test]
+    fn checks_patterns_up_front() {
+        assert!(check_pattern("%d %B %Y").is_ok());
+        assert_eq!(
+            check_pattern("%Q").unwrap_err().to_string(),
+            "DateError::Pattern(%Q)"
+        );
+    }
+}
@@ -4,7 +4,7 @@ use url::Url;
 
 use crate::files;
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[allow(unused)]
 pub enum Href {
     Unparsed(String),
@@ -57,7 +57,7 @@ impl Display for HrefError {
 
 impl std::error::Error for HrefError {}
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub enum Element {
     BlockQuote(Group),
     CodeBlock(Code),
@@ -70,25 +70,51 @@ pub enum Element {
     HrefReference(HrefReference),
     ImageReference(ImageReference),
     InlineCode(Code),
+    // a hard break inside of a paragraph
+    LineBreak,
     List(List),
     Paragraph(Group),
+    // html written directly in the page, already run through the page's html policy
+    RawHtml(String),
+    // a thematic break between blocks
+    Rule,
     Strong(Group),
     Table(Table),
     Text(Text),
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct List {
     li: Vec<ListItem>,
+    // the number of the first item for ordered lists
+    start: Option<u32>,
+    // items of a loose list are separated by blank lines and keep their paragraphs
+    spread: bool,
 }
 
 impl Default for List {
     fn default() -> Self {
-        List { li: vec![] }
+        List {
+            li: vec![],
+            start: None,
+            spread: false,
+        }
     }
 }
 
 impl List {
+    pub fn ordered(start: u32) -> List {
+        List {
+            start: Some(start),
+            ..Default::default()
+        }
+    }
+
+    pub fn spread(&mut self, spread: bool) -> &mut Self {
+        self.spread = spread;
+        self
+    }
+
     pub fn push(&mut self, item: ListItem) {
         self.li.push(item);
     }
@@ -96,24 +122,50 @@ impl List {
     pub fn items(&self) -> impl Iterator<Item = &ListItem> {
         self.li.iter()
     }
+
+    pub fn start(&self) -> Option<u32> {
+        self.start
+    }
+
+    pub fn is_tight(&self) -> bool {
+        !self.spread
+    }
 }
 
-#[derive(Debug)]
-pub struct ListItem(Group);
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ListItem {
+    content: Group,
+    // task list items are either checked or not, everything else is neither
+    checked: Option<bool>,
+}
 
 impl ListItem {
+    pub fn task(content: Group, checked: bool) -> ListItem {
+        ListItem {
+            content,
+            checked: Some(checked),
+        }
+    }
+
     pub fn children(&self) -> &Group {
-        &self.0
+        &self.content
+    }
+
+    pub fn checked(&self) -> Option<bool> {
+        self.checked
     }
 }
 
 impl From<Group> for ListItem {
     fn from(value: Group) -> Self {
-        ListItem(value)
+        ListItem {
+            content: value,
+            checked: None,
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Group {
     kids: Vec<Element>,
 }
@@ -134,7 +186,7 @@ impl Group {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct CodeLiteral(String);
 
 impl CodeLiteral {
@@ -155,7 +207,7 @@ impl AsRef<str> for CodeLiteral {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct CodeLanguage(String);
 
 impl From<String> for CodeLanguage {
@@ -164,26 +216,66 @@ impl From<String> for CodeLanguage {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Code {
     code: CodeLiteral,
     lang: Option<CodeLanguage>,
+    #[serde(default)]
+    options: CodeOptions,
+}
+
+// everything after the language in a fenced block's info string
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct CodeOptions {
+    pub(crate) linenos: bool,
+    // the first line's number, code pulled out of the middle of a file keeps the file's numbering
+    pub(crate) start: usize,
+    // inclusive and counted from the first line of the block
+    pub(crate) hl_lines: Vec<(usize, usize)>,
+    pub(crate) title: Option<String>,
+}
+
+impl CodeOptions {
+    pub fn highlighted(&self, line: usize) -> bool {
+        self.hl_lines
+            .iter()
+            .any(|(from, to)| *from <= line && line <= *to)
+    }
 }
 
 impl Code {
     pub fn new(code: CodeLiteral, lang: Option<CodeLanguage>) -> Code {
-        Code { code, lang }
+        Code {
+            code,
+            lang,
+            options: Default::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: CodeOptions) -> Code {
+        self.options = options;
+        self
     }
 
     pub fn lang(&self) -> &Option<CodeLanguage> {
         &self.lang
     }
 
+    pub fn options(&self) -> &CodeOptions {
+        &self.options
+    }
+
     pub fn content(&self) -> &CodeLiteral {
         &self.code
     }
 }
 
+impl AsRef<str> for CodeLanguage {
+    fn as_ref(&self) -> &str {
+        self.0.as_ref()
+    }
+}
+
 impl Display for CodeLanguage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -217,22 +309,33 @@ impl From<Group> for Element {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Header {
     depth: u8,
+    content: Group,
+    // the content without any markup, for tables of contents, titles and the like
     display: String,
     id: String,
 }
 
 impl Header {
-    pub fn create(depth: u8, display: String, id: String) -> Self {
-        Header { depth, display, id }
+    pub fn create(depth: u8, content: Group, display: String, id: String) -> Self {
+        Header {
+            depth,
+            content,
+            display,
+            id,
+        }
     }
 
     pub fn depth(&self) -> u8 {
         self.depth
     }
 
+    pub fn children(&self) -> &Group {
+        &self.content
+    }
+
     pub fn text(&self) -> &str {
         &self.display
     }
@@ -242,16 +345,13 @@ impl Header {
     }
 }
 
+#[derive(serde::Serialize, serde::Deserialize)]
 pub struct Text(String);
 
 impl Text {
     pub fn create(s: String) -> Text {
         Text(s)
     }
-
-    pub fn inner(self) -> String {
-        self.0
-    }
 }
 
 impl AsRef<str> for Text {
@@ -266,7 +366,7 @@ impl Debug for Text {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct HrefDefinition {
     label: String,
     href_: Href,
@@ -285,7 +385,7 @@ impl HrefDefinition {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct HrefReference {
     content: Group,
     label: String,
@@ -301,10 +401,9 @@ impl HrefReference {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ImageReference {
     href_label: String,
-    #[allow(unused)]
     alt: String,
 }
 
@@ -315,17 +414,33 @@ impl ImageReference {
             alt,
         }
     }
+
+    pub fn alt(&self) -> &str {
+        &self.alt
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Alignment {
+    None,
+    Left,
+    Right,
+    Center,
+}
+
+// the first row is the table's head
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Table {
     r: Vec<TableRow>,
+    // one per column
+    align: Vec<Alignment>,
 }
 
 impl Table {
-    pub fn new() -> Table {
+    pub fn aligned(align: Vec<Alignment>) -> Table {
         Self {
             r: Default::default(),
+            align,
         }
     }
 
@@ -333,12 +448,20 @@ impl Table {
         self.r.push(row);
     }
 
-    pub fn rows(&self) -> impl Iterator<Item = &TableRow> {
-        self.r.iter()
+    pub fn head(&self) -> Option<&TableRow> {
+        self.r.first()
+    }
+
+    pub fn body(&self) -> impl Iterator<Item = &TableRow> {
+        self.r.iter().skip(1)
+    }
+
+    pub fn alignment(&self, column: usize) -> Alignment {
+        self.align.get(column).copied().unwrap_or(Alignment::None)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct TableRow {
     c: Vec<TableCell>,
 }
@@ -357,7 +480,7 @@ impl TableRow {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct TableCell(Group);
 
 impl TableCell {
@@ -370,7 +493,7 @@ impl TableCell {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct FootnoteReference(String);
 
 impl Display for FootnoteReference {
@@ -391,7 +514,7 @@ impl From<FootnoteReference> for Element {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct FootnoteDefinition {
     label: String,
     content: Group,
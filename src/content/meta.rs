@@ -1,10 +1,32 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-#[derive(Debug)]
+// untagged so it reads naturally wherever it ends up serialized, e.g. templates
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
 pub enum Metadata {
     List(Vec<Metadata>),
-    Map(HashMap<String, Metadata>),
+    // ordered so anything derived from it, e.g. the cache fingerprint, is the same every run
+    Map(BTreeMap<String, Metadata>),
     Number(f64),
     Str(String),
     Bool(bool),
 }
+
+// whole numbers come out as integers so templates don't print `3.0` for `3`
+impl From<&Metadata> for minijinja::Value {
+    fn from(value: &Metadata) -> Self {
+        match value {
+            Metadata::List(l) => l.iter().map(minijinja::Value::from).collect(),
+            Metadata::Map(m) => m
+                .iter()
+                .map(|(k, v)| (k.as_str(), minijinja::Value::from(v)))
+                .collect(),
+            Metadata::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                minijinja::Value::from(*n as i64)
+            }
+            Metadata::Number(n) => minijinja::Value::from(*n),
+            Metadata::Str(s) => minijinja::Value::from(s.as_str()),
+            Metadata::Bool(b) => minijinja::Value::from(*b),
+        }
+    }
+}
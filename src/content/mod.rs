@@ -1,17 +1,21 @@
 pub mod corpus;
+pub mod date;
 pub mod definitions;
 pub mod doctree;
 mod meta;
 pub mod origin;
 pub mod page;
 mod pagebuilder;
+pub mod section;
 
-pub use corpus::{Corpus, CorpusEntry, IncludedPath};
+pub use corpus::{Corpus, CorpusEntry};
+pub use date::Date;
 pub use definitions::Definitions;
 pub use meta::Metadata;
 pub use origin::Origin;
-pub use page::{Page, PageContents};
+pub use page::{Page, PageContents, PageKind, PageMetadata};
 pub use pagebuilder::PageBuilder;
+pub use section::Section;
 
 #[derive(Debug)]
 pub enum Error {}
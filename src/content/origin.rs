@@ -46,6 +46,13 @@ struct OriginVisitor;
 
 impl<'de> Visitor<'de> for OriginVisitor {
     type Value = Origin;
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        self.visit_string(v.to_owned())
+    }
+
     fn visit_string<E>(self, v: String) -> std::result::Result<Self::Value, E>
     where
         E: serde::de::Error,
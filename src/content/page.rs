@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use crate::ids;
 
-use super::{doctree, CorpusEntry, Definitions, Metadata, Origin};
+use super::{doctree, CorpusEntry, Definitions, Metadata, Origin, Section};
 
 #[derive(Debug)]
 pub struct Page {
@@ -11,19 +11,32 @@ pub struct Page {
     pub(crate) content: PageContents,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct PageMetadata {
     pub(crate) title: String,
     pub(crate) origin: Origin,
-    pub(crate) when: Option<String>,
+    pub(crate) section: Section,
+    pub(crate) kind: PageKind,
+    pub(crate) when: Option<super::Date>,
     pub(crate) tpl_name: String,
     pub(crate) meta: HashMap<String, Metadata>,
     pub(crate) summary: Option<doctree::Group>,
+    // hash of the source the page was built from
+    pub(crate) checksum: u64,
+    // other files the page was built from, e.g. included code, with their checksums
+    pub(crate) includes: Vec<(PathBuf, u64)>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct PageContents {
     pub(crate) content: Vec<doctree::Element>,
     pub(crate) footnotes: Definitions<doctree::FootnoteDefinition>,
     pub(crate) hrefs: Definitions<doctree::HrefDefinition>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PageKind {
+    Article,
+    // a section's `_index` page, rendered as a listing of everything in the section
+    Section,
+}
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use super::definitions::Definitions;
 use super::doctree;
-use super::page::{Page, PageContents, PageMetadata};
+use super::page::{Page, PageContents, PageKind, PageMetadata};
+use super::section::{Section, SECTION_INDEX};
 use super::{CorpusEntry, Metadata};
 use crate::{files, ids};
 
@@ -11,27 +12,66 @@ pub struct PageBuilder {
     pub(crate) title: String,
     pub(crate) contents: Vec<doctree::Element>,
     pub(crate) filepath: files::FilePath,
+    pub(crate) section: Section,
+    pub(crate) kind: PageKind,
     pub(crate) notes: Definitions<doctree::FootnoteDefinition>,
     pub(crate) page_hrefs: Definitions<doctree::HrefDefinition>,
-    pub(crate) when: Option<String>,
+    pub(crate) when: Option<super::Date>,
     pub(crate) tpl_name: String,
     pub(crate) meta: HashMap<String, Metadata>,
     pub(crate) summary: Option<doctree::Group>,
+    pub(crate) checksum: u64,
+    pub(crate) includes: Vec<(PathBuf, u64)>,
+}
+
+// a page that was built earlier, e.g. pulled from the cache, reopened so processors can have
+// their say on it
+impl From<Page> for PageBuilder {
+    fn from(page: Page) -> Self {
+        PageBuilder {
+            id: page.id,
+            title: page.meta.title,
+            contents: page.content.content,
+            filepath: page.meta.origin.0,
+            section: page.meta.section,
+            kind: page.meta.kind,
+            notes: page.content.footnotes,
+            page_hrefs: page.content.hrefs,
+            when: page.meta.when,
+            tpl_name: page.meta.tpl_name,
+            meta: page.meta.meta,
+            summary: page.meta.summary,
+            checksum: page.meta.checksum,
+            includes: page.meta.includes,
+        }
+    }
 }
 
 impl PageBuilder {
     pub fn new<F: Into<files::FilePath>>(id: ids::Id<CorpusEntry>, f: F) -> PageBuilder {
+        let filepath = f.into();
+        let kind = match filepath.file_stem() {
+            Some(stem) if stem == SECTION_INDEX => PageKind::Section,
+            _ => PageKind::Article,
+        };
         PageBuilder {
             id,
-            filepath: f.into(),
+            filepath,
+            section: Section::root(),
+            kind,
             title: Default::default(),
             contents: Default::default(),
             notes: Default::default(),
             page_hrefs: Default::default(),
             when: Default::default(),
-            tpl_name: "page.html".to_owned(),
+            tpl_name: match kind {
+                PageKind::Article => "page.html".to_owned(),
+                PageKind::Section => "section.html".to_owned(),
+            },
             meta: Default::default(),
             summary: Default::default(),
+            checksum: Default::default(),
+            includes: Default::default(),
         }
     }
 
@@ -40,7 +80,18 @@ impl PageBuilder {
         self
     }
 
-    pub fn written(&mut self, d: String) -> &mut Self {
+    pub fn in_section(&mut self, section: Section) -> &mut Self {
+        self.section = section;
+        self
+    }
+
+    // another file that went into building the page and its checksum
+    pub fn depends_on<P: Into<PathBuf>>(&mut self, path: P, checksum: u64) -> &mut Self {
+        self.includes.push((path.into(), checksum));
+        self
+    }
+
+    pub fn written(&mut self, d: super::Date) -> &mut Self {
         self.when = Some(d);
         self
     }
@@ -67,15 +118,24 @@ impl PageBuilder {
     }
 
     pub fn build(mut self) -> crate::Result<Page> {
+        // untitled sections are named after their directory
+        if self.title.is_empty() && self.kind == PageKind::Section {
+            self.title = self.section.name().unwrap_or_default().to_owned();
+        }
+
         Ok(Page {
             id: self.id,
             meta: PageMetadata {
                 title: self.title,
                 origin: super::Origin(self.filepath),
+                section: self.section,
+                kind: self.kind,
                 when: self.when.take(),
                 tpl_name: self.tpl_name,
                 meta: self.meta,
                 summary: self.summary,
+                checksum: self.checksum,
+                includes: self.includes,
             },
             content: PageContents {
                 content: self.contents,
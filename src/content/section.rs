@@ -0,0 +1,66 @@
+use std::{fmt::Display, path};
+
+// file name, without extension, of the page that introduces a section
+pub const SECTION_INDEX: &str = "_index";
+
+// where a page lives relative to the content base, pages directly in the base are in the root
+// section which has no parts
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub struct Section(Vec<String>);
+
+impl Section {
+    pub fn root() -> Section {
+        Self::default()
+    }
+
+    // the section of the directory `file` is in, anything outside of `base` is placed in the root
+    pub fn of<B: AsRef<path::Path>, F: AsRef<path::Path>>(base: B, file: F) -> Section {
+        let parts = file
+            .as_ref()
+            .parent()
+            .and_then(|dir| dir.strip_prefix(base).ok())
+            .map(|rel| {
+                rel.components()
+                    .filter_map(|c| match c {
+                        path::Component::Normal(p) => Some(p.to_string_lossy().into_owned()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self(parts)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.0.last().map(String::as_str)
+    }
+
+    pub fn parent(&self) -> Option<Section> {
+        self.0.split_last().map(|(_, rest)| Self(rest.to_vec()))
+    }
+
+    pub fn as_path(&self) -> path::PathBuf {
+        self.0.iter().collect()
+    }
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("/"))
+    }
+}
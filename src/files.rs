@@ -1,4 +1,10 @@
-use std::{fmt::Display, ops::Deref, path};
+use std::{fmt::Display, ops::Deref, path, sync::Arc};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+
+// gitignore syntax, applies to the directory it's in and everything below it
+pub const IGNORE_FILE: &str = ".donterignore";
 
 pub enum RecursionBehavior {
     Recurse,
@@ -8,27 +14,119 @@ pub enum RecursionBehavior {
 pub struct Walker<'a> {
     base: &'a DirPath,
     recurse: RecursionBehavior,
+    filter: Filter,
 }
 
 impl<'a> Walker<'a> {
     pub fn walk(base: &'a DirPath, recurse: RecursionBehavior) -> impl Iterator<Item = FilePath> {
-        Self { base, recurse }.into_iter()
+        Self::filtered(base, recurse, Filter::default())
+    }
+
+    pub fn filtered(
+        base: &'a DirPath,
+        recurse: RecursionBehavior,
+        filter: Filter,
+    ) -> impl Iterator<Item = FilePath> {
+        Self {
+            base,
+            recurse,
+            filter,
+        }
+        .into_iter()
+    }
+}
+
+// decides what a walk yields, patterns are globs matched against the whole path as it's walked and
+// an excluded directory isn't descended into. hidden files are skipped unless asked for
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    hidden: bool,
+}
+
+impl Filter {
+    pub fn new<S: AsRef<str>>(exclude: &[S]) -> crate::Result<Filter> {
+        Ok(Self {
+            exclude: globs(exclude)?,
+            ..Default::default()
+        })
+    }
+
+    // only files matching one of these are yielded, directories are always walked
+    pub fn include<S: AsRef<str>>(mut self, include: &[S]) -> crate::Result<Filter> {
+        self.include = globs(include)?;
+        Ok(self)
+    }
+
+    // dot files and directories are walked like anything else, excludes and ignores still apply
+    pub fn include_hidden(mut self, hidden: bool) -> Filter {
+        self.hidden = hidden;
+        self
+    }
+
+    fn allows(&self, path: &Path, ignores: &[Arc<Gitignore>]) -> bool {
+        let (p, is_dir): (&path::Path, _) = match path {
+            Path::File(f) => (f, false),
+            Path::Dir(d) => (d.as_ref(), true),
+        };
+
+        // dot files are editor droppings, version control and the like
+        let hidden = p
+            .file_name()
+            .map(|name| name.to_string_lossy().starts_with('.'))
+            .unwrap_or(false);
+        if hidden && !self.hidden {
+            return false;
+        }
+
+        if matches!(&self.exclude, Some(ex) if ex.is_match(p)) {
+            return false;
+        }
+
+        // the closest ignore file has the final say
+        for ignore in ignores.iter().rev() {
+            let matched = ignore.matched(p, is_dir);
+            if matched.is_ignore() {
+                return false;
+            }
+            if matched.is_whitelist() {
+                break;
+            }
+        }
+
+        match &self.include {
+            Some(inc) if !is_dir => inc.is_match(p),
+            _ => true,
+        }
     }
 }
 
+fn globs<S: AsRef<str>>(patterns: &[S]) -> crate::Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut set = GlobSetBuilder::new();
+    for pattern in patterns {
+        set.add(Glob::new(pattern.as_ref())?);
+    }
+    Ok(Some(set.build()?))
+}
+
 impl<'a> IntoIterator for Walker<'a> {
     type Item = FilePath;
     type IntoIter = BreadthFirstWalker;
     fn into_iter(self) -> Self::IntoIter {
         BreadthFirstWalker {
             recurse: self.recurse,
-            dirs: vec![self.base.clone()],
+            filter: self.filter,
+            dirs: vec![(self.base.clone(), Vec::new())],
             files: Vec::new(),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Path {
     File(FilePath),
     Dir(DirPath),
@@ -84,7 +182,9 @@ impl From<DirPath> for Path {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(
+    Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, serde::Serialize, serde::Deserialize,
+)]
 pub struct FilePath(path::PathBuf);
 
 impl Display for FilePath {
@@ -110,7 +210,7 @@ impl FilePath {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct DirPath(path::PathBuf);
 
 impl DirPath {
@@ -140,12 +240,6 @@ impl std::fmt::Display for PathError {
 
 impl std::error::Error for PathError {}
 
-impl From<path::PathBuf> for Path {
-    fn from(value: path::PathBuf) -> Self {
-        Self::parse(value).unwrap()
-    }
-}
-
 impl AsRef<path::Path> for DirPath {
     fn as_ref(&self) -> &path::Path {
         AsRef::<path::Path>::as_ref(&self.0)
@@ -166,7 +260,9 @@ impl Display for DirPath {
 
 pub struct BreadthFirstWalker {
     recurse: RecursionBehavior,
-    dirs: Vec<DirPath>,
+    filter: Filter,
+    // every directory carries the ignore files of the directories above it
+    dirs: Vec<(DirPath, Vec<Arc<Gitignore>>)>,
     files: Vec<FilePath>,
 }
 
@@ -176,16 +272,21 @@ impl BreadthFirstWalker {
     }
 
     fn reload(&mut self) {
-        if matches!(self.recurse, RecursionBehavior::Dont if self.dirs.len() == 0) {
-            return;
-        }
-
         loop {
-            let dir = match self.dirs.pop() {
+            let (dir, mut ignores) = match self.dirs.pop() {
                 None => break,
                 Some(d) => d,
             };
 
+            let ignore_file = dir.join(IGNORE_FILE);
+            if ignore_file.is_file() {
+                let (ignore, err) = Gitignore::new(&ignore_file);
+                if let Some(err) = err {
+                    eprintln!("problem reading {}: {}", ignore_file.display(), err);
+                }
+                ignores.push(Arc::new(ignore));
+            }
+
             let entries = match std::fs::read_dir(dir) {
                 Err(_) => continue,
                 Ok(e) => e,
@@ -197,9 +298,13 @@ impl BreadthFirstWalker {
                     Ok(e) => e,
                 };
                 match Path::parse(entry.path()) {
-                    Ok(p) => match p {
+                    Ok(p) if self.filter.allows(&p, &ignores) => match p {
                         Path::File(f) => self.files.push(f),
-                        Path::Dir(d) => self.dirs.push(d),
+                        Path::Dir(d) => {
+                            if matches!(self.recurse, RecursionBehavior::Recurse) {
+                                self.dirs.push((d, ignores.clone()))
+                            }
+                        }
                     },
                     _ => {}
                 }
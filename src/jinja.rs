@@ -1,8 +1,8 @@
-use std::{cell::RefCell, io::Read, mem, rc::Rc};
+use std::{io::Read, mem, sync::Arc};
 
 use minijinja;
 
-use crate::{files, site};
+use crate::{config, content, files, site};
 
 pub struct JinjaConfiguration<'a>(pub &'a files::DirPath);
 
@@ -18,6 +18,45 @@ impl<'a> site::Processor for JinjaConfiguration<'a> {
     }
 }
 
+// `{{ page.date|date }}` formats a page date with the site's pattern and timezone,
+// `{{ page.date|date("%B %Y") }}` with any other pattern
+pub struct DateFilter<'a>(pub &'a config::Localization);
+
+impl<'a> site::Processor for DateFilter<'a> {
+    fn initialize<'call, 'init>(
+        &'call mut self,
+        site: &'call mut site::Initializer<'init, '_>,
+    ) -> crate::Result<()>
+    where
+        'init: 'call,
+    {
+        let localization = self.0.clone();
+        site.configure_renderer(|renderer| {
+            renderer.configure(|env| {
+                env.add_filter(
+                    "date",
+                    move |value: String,
+                          pattern: Option<String>|
+                          -> Result<String, minijinja::Error> {
+                        let invalid = |e: content::date::DateError| {
+                            minijinja::Error::new(
+                                minijinja::ErrorKind::InvalidOperation,
+                                e.to_string(),
+                            )
+                        };
+                        let pattern = pattern.unwrap_or_else(|| localization.date_pattern.clone());
+                        content::date::check_pattern(&pattern).map_err(invalid)?;
+                        let date =
+                            content::Date::parse(&value, localization.timezone).map_err(invalid)?;
+                        Ok(date.format(&pattern, localization.timezone))
+                    },
+                );
+                Ok(())
+            })
+        })
+    }
+}
+
 pub struct Builder<'builder, 'env>(&'builder mut minijinja::Environment<'env>)
 where
     'env: 'builder;
@@ -63,7 +102,7 @@ pub struct Renderer<'rendering, 'env>
 where
     'env: 'rendering,
 {
-    r: &'rendering Rc<RefCell<minijinja::Environment<'env>>>,
+    r: &'rendering Arc<minijinja::Environment<'env>>,
     globals: RenderContext,
 }
 
@@ -71,20 +110,20 @@ impl<'rendering, 'env> Renderer<'rendering, 'env>
 where
     'env: 'rendering,
 {
-    pub fn new(
-        r: &'rendering Rc<RefCell<minijinja::Environment<'env>>>,
-        globals: RenderContext,
-    ) -> Self {
+    pub fn new(r: &'rendering Arc<minijinja::Environment<'env>>, globals: RenderContext) -> Self {
         Self { r, globals }
     }
 
+    pub fn has_template(&self, template: &str) -> bool {
+        self.r.get_template(template).is_ok()
+    }
+
     pub fn render_template(
-        &mut self,
+        &self,
         template: &str,
         mut values: RenderContext,
     ) -> crate::Result<String> {
-        let eng = self.r.borrow_mut();
-        let tpl = eng.get_template(template)?;
+        let tpl = self.r.get_template(template)?;
         values.merge(minijinja::context! { globals => &self.globals });
         Ok(tpl.render(values)?)
     }
@@ -7,54 +7,260 @@ mod jinja;
 mod md;
 mod processors;
 mod render;
+mod scaffold;
+mod server;
 mod site;
+mod slug;
+mod watch;
 mod writers;
 
 use std::borrow::Cow;
 use std::error::Error;
+use std::time::Duration;
 
 use clap::Parser;
-use processors::{Archive, StaticFiles, TagArchivist, TagSorting};
+use processors::{Archive, DateArchivist, Index, StaticFiles, TermArchivist};
 use site::PageTemplate;
 
-type Result<T> = std::result::Result<T, Box<dyn Error>>;
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
 
 fn main() -> Result<()> {
-    let mut conf = cli::Args::parse().make_config();
-    let mut app = site::Builder::new()
+    use cli::{Command, NewCommand};
+    match cli::Cli::parse().command {
+        Command::Generate(args) => generate(&args.site.make_config()?, args.watch),
+        Command::Check(args) => check(&args.make_config()?),
+        Command::Serve(args) => serve(&args.make_config()?),
+        Command::New(NewCommand::Site { path }) => scaffold::new_site(path),
+        Command::New(NewCommand::Post { title, site }) => {
+            scaffold::new_post(&site.content_dir()?, title)
+        }
+    }
+}
+
+// the pipeline every command shares, `writes` controls whether anything may touch the output
+fn build(conf: &config::Configuration, writes: bool) -> Result<site::App<'_>> {
+    // any change to the configuration could change how pages are built
+    let fingerprint = site::cache::checksum(&(
+        env!("CARGO_PKG_VERSION"),
+        site::cache::canonical_json(conf)?,
+    ));
+    let cache = match &conf.cache {
+        None => None,
+        Some(dir) => Some(site::Cache::open(dir, fingerprint)?),
+    };
+
+    let indexer = conf.transformers.indexer()?;
+    let home_per_page = conf.transformers.per_page("index")?.unwrap_or(10);
+    let toc_depth = conf.transformers.toc_depth()?;
+    let tag_key = config::tag_key(&conf.taxonomies);
+    let mut builder = site::Builder::new()
+        .cache(cache)
+        .page_filter(conf.content.page_filter()?)
+        .highlighter(conf.rendering.highlighter()?)
+        .anchors(conf.rendering.heading_anchors)
         .linker(site::LinkerOptions {
             site_base: Cow::Borrowed(&conf.site.base_url),
             slug_style: conf.rendering.slug_style,
-            page_root: conf.rendering.page_root.take(),
-            slug_source: site::ArticleSlugSource::Filename,
+            page_root: conf.rendering.page_root.clone(),
+            preserve_structure: conf.content.preserve_structure,
         })
-        .with(processors::Toc { depth: 3 })
-        .with(processors::Tags)
-        .with_when(conf.content.assets.is_some(), || {
-            StaticFiles(conf.content.assets.clone().unwrap().into())
+        .with_when(conf.transformers.enabled("toc", true), || processors::Toc {
+            depth: toc_depth,
         })
-        .with_when(conf.output.clean, || {
+        .with_when(conf.transformers.enabled("tags", true), || {
+            processors::Terms(&conf.taxonomies)
+        });
+
+    for include in conf.content.include.iter() {
+        builder = builder.with(StaticFiles::new(
+            include.path.clone(),
+            include.as_.as_deref(),
+            conf.content.asset_filter()?,
+        ));
+    }
+
+    builder = builder
+        .with_when(writes && conf.output.clean, || {
             processors::Cleaner(conf.output.output.clone())
         })
         .with(jinja::JinjaConfiguration(&conf.site.templates))
-        .with(md::Md)
-        .with(Archive::new(
-            TagArchivist(TagSorting::Alphabetical),
-            PageTemplate {
-                title: "Tag Archive",
-                url: unsafe { files::FilePath::new("tags.html") },
-                template: "tags.html",
-            },
-        ))
-        .create()?;
+        .with(md::Md {
+            html: conf.content.raw_html,
+            timezone: conf.rendering.localization.timezone,
+        })
+        .with(jinja::DateFilter(&conf.rendering.localization))
+        .with_when(conf.transformers.enabled("dates", false), || {
+            Archive::new(
+                DateArchivist::new(
+                    &conf.rendering.localization,
+                    indexer.date_grouping,
+                    indexer.date_sorting,
+                ),
+                PageTemplate {
+                    title: "Archive",
+                    url: unsafe { files::FilePath::new("archive.html") },
+                    template: "archive.html",
+                },
+            )
+            .with_bucket_pages("archive_period.html")
+            .paginate(indexer.per_page)
+            .with_tag_key(tag_key)
+        })
+        .with_when(conf.transformers.enabled("index", true), || {
+            Index::new(
+                PageTemplate {
+                    title: "Home",
+                    url: unsafe { files::FilePath::new("index.html") },
+                    template: "index.html",
+                },
+                home_per_page,
+                tag_key,
+            )
+        });
+
+    if conf.transformers.enabled("archive", true) {
+        for taxonomy in conf.taxonomies.iter() {
+            builder = builder.with(
+                Archive::new(
+                    TermArchivist::new(
+                        &taxonomy.key,
+                        taxonomy.ordering,
+                        taxonomy.sorting,
+                        &taxonomy.pattern,
+                    ),
+                    PageTemplate {
+                        title: &taxonomy.title,
+                        url: unsafe { files::FilePath::new(&taxonomy.index_url) },
+                        template: &taxonomy.index_template,
+                    },
+                )
+                .with_bucket_pages(&taxonomy.term_template)
+                .paginate(indexer.per_page)
+                .with_tag_key(tag_key),
+            );
+        }
+    }
 
+    let feeds: Vec<processors::FeedFormat> = [
+        ("atom", processors::FeedFormat::Atom),
+        ("rss", processors::FeedFormat::Rss),
+        ("json-feed", processors::FeedFormat::Json),
+    ]
+    .into_iter()
+    .filter(|(name, _)| conf.transformers.enabled(name, false))
+    .map(|(_, format)| format)
+    .collect();
+    // after the archives so term feeds can point at the term pages that were rendered
+    if !feeds.is_empty() {
+        builder = builder.with(processors::Feeds::new(
+            conf.transformers.feeds()?,
+            &conf.site.base_url,
+            feeds,
+            &conf.taxonomies,
+            conf.rendering.inline_highlighter()?,
+        ));
+    }
+
+    // last so it sees every page the other processors rendered
+    if conf.transformers.enabled("sitemap", false) {
+        builder = builder.with(processors::Sitemap::new(
+            &conf.site.base_url,
+            conf.transformers.sitemap()?,
+        ));
+    }
+
+    builder.create()
+}
+
+// loads, processes and renders the whole site into `writer`
+fn run(
+    app: &mut site::App<'_>,
+    conf: &config::Configuration,
+    mut writer: Box<dyn site::Writer>,
+) -> Result<()> {
     let mut corpus = content::Corpus::create(1312);
     app.load(&conf.content.base(), &mut corpus)?;
     app.process(&mut corpus)?;
-    let mut writer = conf.output.writer()?;
-    writer.write(app.render(&corpus)?)?;
+    app.render(&corpus, writer.as_mut())?;
+    writer.flush()
+}
+
+// rebuilds into a fresh writer every time something changes until the process is killed
+fn watch<W, D>(
+    app: &mut site::App<'_>,
+    conf: &config::Configuration,
+    mut writer: W,
+    mut rebuilt: D,
+) -> Result<()>
+where
+    W: FnMut() -> Result<Box<dyn site::Writer>>,
+    D: FnMut(),
+{
+    let watcher = watch::Watcher::new(conf.watched(), Duration::from_millis(250))?;
+    loop {
+        let changed = watcher.wait()?;
+        println!("{} paths changed, rebuilding", changed.len());
+        let result = app
+            .reload()
+            .and_then(|_| writer())
+            .and_then(|writer| run(app, conf, writer));
+        match result {
+            Ok(_) => {
+                println!("rebuilt");
+                rebuilt();
+            }
+            Err(e) => eprintln!("rebuild failed: {}", e),
+        }
+        // runs even after a failed rebuild so the next one starts from clean processors
+        if let Err(e) = app.finalize() {
+            eprintln!("cleaning up after rebuild failed: {}", e);
+        }
+    }
+}
+
+fn generate(conf: &config::Configuration, watching: bool) -> Result<()> {
+    let mut app = build(conf, true)?;
+    run(&mut app, conf, conf.output.writer()?)?;
     app.finalize()?;
-    writer.flush()?;
     println!("all done!");
+
+    if watching {
+        watch(&mut app, conf, || conf.output.writer(), || {})?;
+    }
+    Ok(())
+}
+
+fn check(conf: &config::Configuration) -> Result<()> {
+    let mut app = build(conf, false)?;
+    let mut corpus = content::Corpus::create(1312);
+    app.load(&conf.content.base(), &mut corpus)?;
+    app.process(&mut corpus)?;
+    let rendered = app.render(&corpus, &mut writers::Discard)?.pages().count();
+    app.finalize()?;
+    println!("rendered {} pages, no problems found", rendered);
     Ok(())
 }
+
+fn serve(conf: &config::Configuration) -> Result<()> {
+    let site = writers::ServedSite::default();
+    let server = server::Server::new(
+        server::Options {
+            listen: conf.serve.listen.clone(),
+            reload: conf.serve.reload,
+        },
+        site.clone(),
+    );
+    let reloader = server.reloader();
+
+    let mut app = build(conf, false)?;
+    run(&mut app, conf, Box::new(writers::Memory::new(site.clone())))?;
+    app.finalize()?;
+    server.start()?;
+
+    watch(
+        &mut app,
+        conf,
+        || Ok(Box::new(writers::Memory::new(site.clone()))),
+        || reloader.notify(),
+    )
+}
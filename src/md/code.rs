@@ -0,0 +1,236 @@
+use std::path;
+
+use super::Error;
+use crate::content::doctree::CodeOptions;
+
+// code pulled in from a file instead of written in the page
+pub struct Include {
+    pub(crate) path: path::PathBuf,
+    pub(crate) lines: Option<(usize, usize)>,
+}
+
+// a fenced block's info string after the language, e.g.
+// linenos hl_lines="3-5 8" title="main.go" include="../src/x.rs" lines="10-40"
+// options that aren't known are ignored so meta meant for other tools doesn't break the page
+pub fn parse_meta(meta: &str) -> Result<(CodeOptions, Option<Include>), Error> {
+    let mut opts = CodeOptions {
+        start: 1,
+        ..Default::default()
+    };
+    let mut include = None;
+    let mut lines = None;
+
+    for (key, value) in tokenize(meta)? {
+        match (key.as_str(), value) {
+            ("linenos", None) => opts.linenos = true,
+            ("linenos", Some(v)) => opts.linenos = v != "false",
+            ("hl_lines", Some(v)) => opts.hl_lines = ranges(&v)?,
+            ("title", Some(v)) => opts.title = Some(v),
+            ("include", Some(v)) => include = Some(path::PathBuf::from(v)),
+            ("lines", Some(v)) => match ranges(&v)?.as_slice() {
+                [range] => lines = Some(*range),
+                _ => return Err(invalid(format!("lines must be a single range, got {}", v))),
+            },
+            (key @ ("hl_lines" | "title" | "include" | "lines"), None) => {
+                return Err(invalid(format!("{} requires a value", key)))
+            }
+            _ => {}
+        }
+    }
+
+    if lines.is_some() && include.is_none() {
+        return Err(invalid("lines can only be used with include".to_owned()));
+    }
+
+    if let Some((from, _)) = lines {
+        opts.start = from;
+    }
+
+    Ok((opts, include.map(|path| Include { path, lines })))
+}
+
+impl Include {
+    // only the selected lines, numbered from 1
+    pub fn select(&self, source: &str) -> Result<String, Error> {
+        let (from, to) = match self.lines {
+            None => return Ok(source.to_owned()),
+            Some(lines) => lines,
+        };
+        let count = source.lines().count();
+        if to > count {
+            return Err(invalid(format!(
+                "lines {}-{} are past the end of {} which has {} lines",
+                from,
+                to,
+                self.path.display(),
+                count
+            )));
+        }
+        let mut selected = source
+            .lines()
+            .skip(from - 1)
+            .take(to - from + 1)
+            .collect::<Vec<_>>()
+            .join("\n");
+        selected.push('\n');
+        Ok(selected)
+    }
+}
+
+fn tokenize(meta: &str) -> Result<Vec<(String, Option<String>)>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = meta.trim().chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            key.push(c);
+        }
+
+        if chars.next_if_eq(&'=').is_none() {
+            tokens.push((key, None));
+            continue;
+        }
+
+        let mut value = String::new();
+        match chars.next_if(|c| *c == '"' || *c == '\'') {
+            Some(quote) => loop {
+                match chars.next() {
+                    None => return Err(invalid(format!("unterminated quote for {}", key))),
+                    Some(c) if c == quote => break,
+                    Some(c) => value.push(c),
+                }
+            },
+            None => {
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    value.push(c);
+                }
+            }
+        }
+        tokens.push((key, Some(value)));
+    }
+
+    Ok(tokens)
+}
+
+// "3-5,8" or "3-5 8", single numbers are a range of one line
+fn ranges(raw: &str) -> Result<Vec<(usize, usize)>, Error> {
+    raw.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|r| !r.is_empty())
+        .map(|r| {
+            let (from, to) = r.split_once('-').unwrap_or((r, r));
+            let parse = |n: &str| {
+                n.trim()
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| invalid(format!("{} is not a line range", r)))
+            };
+            let (from, to) = (parse(from)?, parse(to)?);
+            if from > to {
+                return Err(invalid(format!("{} is not a line range", r)));
+            }
+            Ok((from, to))
+        })
+        .collect()
+}
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidCodeBlock(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_meta, ranges, Include};
+
+    fn error(meta: &str) -> String {
+        match parse_meta(meta) {
+            Ok(_) => panic!("{:?} should not parse", meta),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_every_option() {
+        let (opts, include) =
+            parse_meta(r#"linenos hl_lines="3-5 8" title='main go.rs' other=thing"#).unwrap();
+        assert!(opts.linenos);
+        assert_eq!(opts.start, 1);
+        assert_eq!(opts.hl_lines, [(3, 5), (8, 8)]);
+        assert_eq!(opts.title.as_deref(), Some("main go.rs"));
+        assert!(include.is_none());
+        assert!(opts.highlighted(4) && opts.highlighted(8));
+        assert!(!opts.highlighted(6));
+
+        let (opts, _) = parse_meta("linenos=false").unwrap();
+        assert!(!opts.linenos);
+        let (opts, _) = parse_meta("").unwrap();
+        assert!(!opts.linenos && opts.hl_lines.is_empty() && opts.title.is_none());
+    }
+
+    #[test]
+    fn included_lines_keep_the_file_numbering() {
+        let (opts, include) = parse_meta("include=../src/x.rs lines=10-40 linenos").unwrap();
+        let include = include.unwrap();
+        assert_eq!(include.path, std::path::Path::new("../src/x.rs"));
+        assert_eq!(include.lines, Some((10, 40)));
+        assert_eq!(opts.start, 10);
+    }
+
+    #[test]
+    fn rejects_broken_meta() {
+        assert_eq!(
+            error("lines=1-2"),
+            "WalkerError::InvalidCodeBlock(lines can only be used with include)"
+        );
+        assert_eq!(
+            error("include=x.rs lines=1-2,5"),
+            "WalkerError::InvalidCodeBlock(lines must be a single range, got 1-2,5)"
+        );
+        assert_eq!(
+            error("title"),
+            "WalkerError::InvalidCodeBlock(title requires a value)"
+        );
+        assert_eq!(
+            error(r#"title="unterminated"#),
+            "WalkerError::InvalidCodeBlock(unterminated quote for title)"
+        );
+    }
+
+    #[test]
+    fn parses_line_ranges() {
+        assert_eq!(ranges("3-5,8").unwrap(), [(3, 5), (8, 8)]);
+        assert_eq!(ranges(" 1  2-2 ").unwrap(), [(1, 1), (2, 2)]);
+        assert!(ranges("").unwrap().is_empty());
+        for bad in ["0", "5-3", "a-b", "1-", "-2"] {
+            assert!(ranges(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn selects_included_lines() {
+        let source = "one\ntwo\nthree\nfour\n";
+        let include = |lines| Include {
+            path: "x.txt".into(),
+            lines,
+        };
+        assert_eq!(include(None).select(source).unwrap(), source);
+        assert_eq!(
+            include(Some((2, 3))).select(source).unwrap(),
+            "two\nthree\n"
+        );
+        assert_eq!(include(Some((4, 4))).select(source).unwrap(), "four\n");
+        assert_eq!(
+            include(Some((3, 5)))
+                .select(source)
+                .unwrap_err()
+                .to_string(),
+            "WalkerError::InvalidCodeBlock(lines 3-5 are past the end of x.txt which has 4 lines)"
+        );
+    }
+}
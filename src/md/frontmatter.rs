@@ -1,9 +1,9 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{collections::BTreeMap, fmt::Display};
 
 use markdown::mdast;
 use yaml_rust2::{yaml::Hash, Yaml, YamlLoader};
 
-use crate::content::{Metadata, PageBuilder};
+use crate::content::{Date, Metadata, PageBuilder, PageKind};
 
 #[derive(Debug)]
 pub struct GenericError(String);
@@ -25,8 +25,10 @@ impl Display for GenericError {
 pub fn frontmatter_to_page_meta(y: &mdast::Yaml, b: &mut PageBuilder) -> crate::Result<()> {
     let docs = YamlLoader::load_from_str(&y.value)
         .map_err(|e| GenericError::with_reason(e, format!("on page {}", b.filepath)))?;
-    b.meta = match &docs[0] {
-        Yaml::Hash(map) => convert_yaml_map(map)?
+    b.meta = match docs.first() {
+        // `---\n---` is an empty document
+        None => Default::default(),
+        Some(Yaml::Hash(map)) => convert_yaml_map(map)?
             .into_iter()
             .map(|(k, v)| (k.to_lowercase(), v))
             .collect(),
@@ -34,34 +36,57 @@ pub fn frontmatter_to_page_meta(y: &mdast::Yaml, b: &mut PageBuilder) -> crate::
             "frontmatter must have object at top level".to_owned(),
         ))?,
     };
+    Ok(())
+}
 
-    b.with_title(
-        b.meta
-            .get("title")
-            .map(|t| match t {
-                Metadata::Str(s) => Ok(s.clone()),
-                _ => Err(super::Error::Unexpected(
-                    "title must be a string".to_owned(),
-                )),
-            })
-            .expect("title key must be provided")?,
-    );
+// pulls title and date out of whatever metadata the page ended up with, checked after the whole
+// page is walked so an article without any frontmatter is caught too
+pub fn required_page_meta(b: &mut PageBuilder, tz: chrono_tz::Tz) -> crate::Result<()> {
+    // section pages describe a directory, they're allowed to go without a title or date
+    let section = b.kind == PageKind::Section;
 
-    b.written(
-        b.meta
-            .get("date")
-            .map(|d| match d {
-                Metadata::Str(s) => Ok(s.clone()),
-                _ => Err(super::Error::Unexpected("date must be a string".to_owned())),
-            })
-            .expect("date key must be provided")?,
-    );
+    let title = b.meta.get("title").map(|t| match t {
+        Metadata::Str(s) => Ok(s.clone()),
+        _ => Err(GenericError::with_reason(
+            "title must be a string",
+            format!("on page {}", b.filepath),
+        )),
+    });
+    match title {
+        Some(title) => {
+            b.with_title(title?);
+        }
+        None if section => {}
+        None => Err(GenericError::with_reason(
+            "title key must be provided",
+            format!("on page {}", b.filepath),
+        ))?,
+    }
+
+    let date = b.meta.get("date").map(|d| match d {
+        Metadata::Str(s) => Date::parse(s, tz)
+            .map_err(|e| GenericError::with_reason(e, format!("on page {}", b.filepath))),
+        _ => Err(GenericError::with_reason(
+            "date must be a string",
+            format!("on page {}", b.filepath),
+        )),
+    });
+    match date {
+        Some(date) => {
+            b.written(date?);
+        }
+        None if section => {}
+        None => Err(GenericError::with_reason(
+            "date key must be provided",
+            format!("on page {}", b.filepath),
+        ))?,
+    }
 
     Ok(())
 }
 
 impl TryFrom<&Yaml> for Metadata {
-    type Error = Box<dyn std::error::Error>;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
     fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
         match value {
             r @ Yaml::Real(_) => Ok(Metadata::Number(r.as_f64().unwrap())),
@@ -81,8 +106,8 @@ impl TryFrom<&Yaml> for Metadata {
     }
 }
 
-fn convert_yaml_map(m: &Hash) -> crate::Result<HashMap<String, Metadata>> {
-    let mut map = HashMap::new();
+fn convert_yaml_map(m: &Hash) -> crate::Result<BTreeMap<String, Metadata>> {
+    let mut map = BTreeMap::new();
 
     for (k, v) in m.iter() {
         match k {
@@ -0,0 +1,412 @@
+use super::Error;
+
+// tags kept by the sanitizer, anything else is dropped while its content is kept
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+    "var",
+];
+
+// attributes kept on any allowed tag
+const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "alt", "class", "colspan", "height", "href", "id", "lang", "rowspan", "src", "start", "title",
+    "width",
+];
+
+// attributes that are urls and so can't be allowed to smuggle in script
+const URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+// tags whose content is dropped along with them
+const DROPPED_WITH_CONTENT: &[&str] = &["script", "style", "iframe", "object", "embed", "template"];
+
+// what happens to html written directly in a page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub enum HtmlPolicy {
+    // written to the page as is
+    #[default]
+    Passthrough,
+    // only an allowlist of tags and attributes survives
+    Sanitize,
+    // the page fails to load
+    Reject,
+}
+
+impl HtmlPolicy {
+    pub fn apply(&self, html: &str) -> Result<String, Error> {
+        match self {
+            HtmlPolicy::Passthrough => Ok(html.to_owned()),
+            HtmlPolicy::Sanitize => Ok(sanitize(html)),
+            HtmlPolicy::Reject => Err(Error::RejectedHtml(html.trim().to_owned())),
+        }
+    }
+}
+
+pub fn sanitize(html: &str) -> String {
+    let mut clean = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut dropping: Option<String> = None;
+
+    while let Some(at) = rest.find('<') {
+        if dropping.is_none() {
+            clean.push_str(&escape_stray(&rest[..at]));
+        }
+        rest = &rest[at..];
+
+        // comments, doctypes and processing instructions never survive
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map(|end| &after[end + 3..]).unwrap_or("");
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map(|end| &rest[end + 1..]).unwrap_or("");
+            continue;
+        }
+
+        let end = match tag_end(rest) {
+            None => {
+                // not actually a tag, just a lone <
+                if dropping.is_none() {
+                    clean.push_str("&lt;");
+                }
+                rest = &rest[1..];
+                continue;
+            }
+            Some(end) => end,
+        };
+        let tag = Tag::parse(&rest[1..end]);
+        rest = &rest[end + 1..];
+
+        let tag = match tag {
+            None => continue,
+            Some(t) => t,
+        };
+
+        if let Some(name) = &dropping {
+            if tag.closing && &tag.name == name {
+                dropping = None;
+            }
+            continue;
+        }
+
+        if DROPPED_WITH_CONTENT.contains(&tag.name.as_str()) {
+            if !tag.closing && !tag.self_closing {
+                dropping = Some(tag.name);
+            }
+            continue;
+        }
+
+        if ALLOWED_TAGS.contains(&tag.name.as_str()) {
+            clean.push_str(&tag.render());
+        }
+    }
+
+    if dropping.is_none() {
+        clean.push_str(&escape_stray(rest));
+    }
+    clean
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl Tag {
+    // the inside of <...>
+    fn parse(raw: &str) -> Option<Tag> {
+        let (closing, raw) = match raw.strip_prefix('/') {
+            Some(r) => (true, r),
+            None => (false, raw),
+        };
+        let (self_closing, raw) = match raw.trim_end().strip_suffix('/') {
+            Some(r) => (true, r),
+            None => (false, raw),
+        };
+
+        let name_end = raw.find(|c: char| c.is_whitespace()).unwrap_or(raw.len());
+        let name = raw[..name_end].to_ascii_lowercase();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return None;
+        }
+
+        Some(Tag {
+            name,
+            closing,
+            self_closing,
+            attributes: attributes(&raw[name_end..]),
+        })
+    }
+
+    fn render(&self) -> String {
+        if self.closing {
+            return format!("</{}>", self.name);
+        }
+
+        let mut out = format!("<{}", self.name);
+        for (name, value) in self.attributes.iter() {
+            if !ALLOWED_ATTRIBUTES.contains(&name.as_str()) {
+                continue;
+            }
+            match value {
+                None => out.push_str(&format!(" {}", name)),
+                Some(value) => {
+                    if URL_ATTRIBUTES.contains(&name.as_str()) && !safe_url(value) {
+                        continue;
+                    }
+                    out.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+                }
+            }
+        }
+        if self.self_closing {
+            out.push_str(" /");
+        }
+        out.push('>');
+        out
+    }
+}
+
+// finds the > closing the tag that starts `s`, skipping any inside of quoted attribute values
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (idx, c) in s.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(idx),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn attributes(raw: &str) -> Vec<(String, Option<String>)> {
+    let mut attrs = Vec::new();
+    let mut chars = raw.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            name.push(c.to_ascii_lowercase());
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        if chars.next_if_eq(&'=').is_none() {
+            attrs.push((name, None));
+            continue;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        match chars.next_if(|c| *c == '"' || *c == '\'') {
+            Some(quote) => {
+                for c in chars.by_ref() {
+                    if c == quote {
+                        break;
+                    }
+                    value.push(c);
+                }
+            }
+            None => {
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    value.push(c);
+                }
+            }
+        }
+        attrs.push((name, Some(value)));
+    }
+
+    attrs
+}
+
+// judged on what the browser ends up with, so character references are decoded first:
+// `&#106;avascript:` and `javascript&colon;` are both javascript urls
+fn safe_url(url: &str) -> bool {
+    let url: String = decode_references(url)
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match url.find([':', '/', '?', '#']) {
+        // relative, or the colon is further along, e.g. in a query string
+        None => true,
+        Some(at) if !url[at..].starts_with(':') => true,
+        // a reference left undecoded could still be hiding part of the scheme
+        Some(at) if url[..at].contains('&') => false,
+        Some(at) => matches!(&url[..at], "http" | "https" | "mailto"),
+    }
+}
+
+// numeric references plus the named ones that can spell out a scheme, anything else is
+// left as written
+fn decode_references(s: &str) -> String {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(at) = rest.find('&') {
+        decoded.push_str(&rest[..at]);
+        rest = &rest[at..];
+        match reference(rest) {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// the character a reference at the start of `s` stands for and how long the reference is,
+// browsers accept numeric references without the closing ;
+fn reference(s: &str) -> Option<(char, usize)> {
+    let body = &s[1..];
+    if let Some(num) = body.strip_prefix('#') {
+        let (digits, radix, skip) = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 16, 3),
+            None => (num, 10, 2),
+        };
+        let len = digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+        if len == 0 {
+            return None;
+        }
+        let c = u32::from_str_radix(&digits[..len], radix)
+            .ok()
+            .and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        let semicolon = digits[len..].starts_with(';') as usize;
+        return Some((c, skip + len + semicolon));
+    }
+
+    let end = body.find(';')?;
+    let c = match &body[..end] {
+        "colon" => ':',
+        "Tab" => '\t',
+        "NewLine" => '\n',
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "sol" => '/',
+        "quest" => '?',
+        "num" => '#',
+        "period" => '.',
+        _ => return None,
+    };
+    Some((c, end + 2))
+}
+
+// text between tags is html already so entities are left alone, only markup is neutralized
+fn escape_stray(text: &str) -> String {
+    text.replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// values came out of html so any entities in them are already fine
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sanitize;
+
+    #[test]
+    fn drops_script_urls_hidden_behind_references() {
+        for payload in [
+            r#"<a href="&#106;avascript:alert(1)">x</a>"#,
+            r#"<a href="&#x6A;avascript:alert(1)">x</a>"#,
+            r#"<a href="&#106avascript:alert(1)">x</a>"#,
+            r#"<a href="javascript&colon;alert(1)">x</a>"#,
+            r#"<a href="java&Tab;script&colon;alert(1)">x</a>"#,
+            r#"<img src="&#32;javascript:alert(1)">"#,
+        ] {
+            let clean = sanitize(payload);
+            assert!(
+                !clean.contains("href") && !clean.contains("src"),
+                "{}",
+                clean
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_ordinary_urls() {
+        assert_eq!(
+            sanitize(r#"<a href="/search?q=a&amp;b=c:d">x</a>"#),
+            r#"<a href="/search?q=a&amp;b=c:d">x</a>"#
+        );
+        assert_eq!(
+            sanitize(r#"<a href="https://example.com/&#35;top">x</a>"#),
+            r#"<a href="https://example.com/&#35;top">x</a>"#
+        );
+        assert_eq!(
+            sanitize(r#"<a href="mailto:me@example.com">x</a>"#),
+            r#"<a href="mailto:me@example.com">x</a>"#
+        );
+    }
+}
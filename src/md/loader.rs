@@ -1,16 +1,25 @@
 use crate::{content, files, site};
 use std::io::Read;
 
-pub struct Loader {}
+pub struct Loader {
+    html: super::HtmlPolicy,
+    timezone: chrono_tz::Tz,
+}
+
+impl Loader {
+    pub fn new(html: super::HtmlPolicy, timezone: chrono_tz::Tz) -> Loader {
+        Loader { html, timezone }
+    }
+}
 
 impl Default for Loader {
     fn default() -> Self {
-        Loader {}
+        Loader::new(Default::default(), chrono_tz::UTC)
     }
 }
 
 impl site::Loader for Loader {
-    fn accept(&mut self, path: &files::FilePath) -> crate::Result<bool> {
+    fn accept(&self, path: &files::FilePath) -> crate::Result<bool> {
         match path.as_path().extension() {
             None => Ok(false),
             Some(ext) => Ok("md" == ext),
@@ -18,7 +27,7 @@ impl site::Loader for Loader {
     }
 
     fn load(
-        &mut self,
+        &self,
         mut content: Box<dyn Read>,
         builder: &mut content::PageBuilder,
     ) -> crate::Result<()> {
@@ -37,6 +46,6 @@ impl site::Loader for Loader {
         };
 
         let node = markdown::to_mdast(&buf, &opts).map_err(|e| Error::ParseError(e))?;
-        MarkdownPageBuilder::new(builder, &opts).build(&node)
+        MarkdownPageBuilder::new(builder, &opts, self.html, self.timezone).build(&node)
     }
 }
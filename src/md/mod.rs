@@ -1,10 +1,18 @@
+mod code;
 mod frontmatter;
+mod html;
 pub mod loader;
+#[cfg(test)]
+mod spec;
 mod walker;
 use crate::site;
+pub use html::HtmlPolicy;
 pub use loader::Loader;
 
-pub struct Md;
+pub struct Md {
+    pub(crate) html: HtmlPolicy,
+    pub(crate) timezone: chrono_tz::Tz,
+}
 
 impl site::Processor for Md {
     fn initialize<'call, 'init>(
@@ -14,7 +22,7 @@ impl site::Processor for Md {
     where
         'init: 'call,
     {
-        site.add_loader(Box::new(Loader::default()));
+        site.add_loader(Box::new(Loader::new(self.html, self.timezone)));
         Ok(())
     }
 }
@@ -23,6 +31,8 @@ impl site::Processor for Md {
 pub enum Error {
     Unexpected(String),
     ParseError(markdown::message::Message),
+    InvalidCodeBlock(String),
+    RejectedHtml(String),
 }
 
 impl std::fmt::Display for Error {
@@ -31,6 +41,8 @@ impl std::fmt::Display for Error {
         match self {
             Self::Unexpected(msg) => write!(f, "Unexpected({})", msg),
             Self::ParseError(msg) => write!(f, "MarkdownParseError({})", msg),
+            Self::InvalidCodeBlock(msg) => write!(f, "InvalidCodeBlock({})", msg),
+            Self::RejectedHtml(html) => write!(f, "RejectedHtml({})", html),
         }
     }
 }
@@ -0,0 +1,490 @@
+// runs the commonmark and gfm spec examples vendored under tests/spec through the markdown loader
+// and the doctree renderer. every suite has a list of the examples it fails next to its fixtures,
+// with how and why each one fails. an example that fails without being listed, or fails another way
+// than recorded, fails the test, and so does a listed example that passes. set DONTER_SPEC_BLESS=1
+// to drop fixed examples from the lists, run with --nocapture to see how each spec section is doing.
+// the fixtures come from commonmark 0.31.2 and the extension sections of the gfm spec, taken from
+// the generated tests shipped with pulldown-cmark 0.13
+use std::collections::{BTreeMap, BTreeSet};
+use std::{fmt, panic, path, sync::Once, thread};
+
+use crate::{content, files, ids, render, site::Loader as _};
+
+const BLESS: &str = "DONTER_SPEC_BLESS";
+const EXAMPLE_THREAD: &str = "spec-example";
+
+#[derive(serde::Deserialize)]
+struct Example {
+    markdown: String,
+    html: String,
+    example: usize,
+    section: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    // rendered, but not what the spec says
+    Differs,
+    // didn't load or panicked
+    Error,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Differs => write!(f, "differs"),
+            Failure::Error => write!(f, "error"),
+        }
+    }
+}
+
+// a line of a failing list, comments are kept so blessing doesn't lose them
+enum Line {
+    Comment(String),
+    Known {
+        examples: BTreeSet<usize>,
+        failure: Failure,
+        reason: String,
+    },
+}
+
+#[test]
+fn commonmark() {
+    conformance("commonmark");
+}
+
+#[test]
+fn gfm() {
+    conformance("gfm");
+}
+
+fn conformance(suite: &str) {
+    let base = path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/spec");
+    let fixtures = base.join(format!("{suite}.json"));
+    let failing_list = base.join(format!("{suite}.failing"));
+
+    let examples: Vec<Example> =
+        serde_json::from_str(&std::fs::read_to_string(&fixtures).unwrap()).unwrap();
+    let mut lines = match std::fs::read_to_string(&failing_list) {
+        Ok(list) => parse_failing_list(&list),
+        Err(_) => vec![],
+    };
+    let mut known = BTreeMap::new();
+    for line in lines.iter() {
+        if let Line::Known {
+            examples,
+            failure,
+            reason,
+        } = line
+        {
+            for example in examples.iter() {
+                let previous = known.insert(*example, (*failure, reason.as_str()));
+                assert!(previous.is_none(), "example {example} is listed twice");
+            }
+        }
+    }
+
+    let mut passing = 0;
+    let mut fixed = BTreeSet::new();
+    let mut sections: Vec<(&str, usize, usize)> = vec![];
+    let mut regressions = vec![];
+    for ex in examples.iter() {
+        let want = normalize(&ex.html);
+        let got = render(&ex.markdown).map(|html| normalize(&html));
+        let failure = match &got {
+            Ok(html) if *html == want => None,
+            Ok(_) => Some(Failure::Differs),
+            Err(_) => Some(Failure::Error),
+        };
+
+        // fixtures are in spec order so sections are too
+        if sections.last().map(|(name, _, _)| *name) != Some(&ex.section) {
+            sections.push((&ex.section, 0, 0));
+        }
+        let entry = sections.last_mut().unwrap();
+        entry.2 += 1;
+
+        let recorded = known.get(&ex.example);
+        match (failure, recorded) {
+            (None, None) => {
+                entry.1 += 1;
+                passing += 1;
+            }
+            (None, Some(_)) => {
+                entry.1 += 1;
+                passing += 1;
+                fixed.insert(ex.example);
+            }
+            (Some(failure), Some((expected, _))) if failure == *expected => {}
+            (Some(failure), recorded) => regressions.push(format!(
+                "example {} ({}) {}\n  markdown: {:?}\n  expected: {}\n  actual:   {}",
+                ex.example,
+                ex.section,
+                match recorded {
+                    None => format!("{failure} but isn't on the failing list"),
+                    Some((expected, reason)) => {
+                        format!("{failure} but is listed as {expected}: {reason}")
+                    }
+                },
+                ex.markdown,
+                want,
+                match got {
+                    Ok(html) => html,
+                    Err(e) => format!("error: {e}"),
+                }
+            )),
+        }
+    }
+
+    println!("{suite}: {}/{} examples pass", passing, examples.len());
+    for (name, pass, total) in sections.iter() {
+        println!("  {:<42} {:>4}/{:<4}", name, pass, total);
+    }
+
+    assert!(
+        regressions.is_empty(),
+        "{} {suite} examples fail without being expected to:\n{}",
+        regressions.len(),
+        regressions.join("\n")
+    );
+
+    if std::env::var_os(BLESS).is_some() {
+        for line in lines.iter_mut() {
+            if let Line::Known { examples, .. } = line {
+                examples.retain(|example| !fixed.contains(example));
+            }
+        }
+        std::fs::write(&failing_list, format_failing_list(&lines)).unwrap();
+    } else {
+        assert!(
+            fixed.is_empty(),
+            "{suite} examples {fixed:?} pass now, take them off {} or rerun with {BLESS}=1",
+            failing_list.display()
+        );
+    }
+}
+
+// the walker panics on a few constructs it doesn't support yet, those count as failures
+fn render(markdown: &str) -> Result<String, String> {
+    static QUIET: Once = Once::new();
+    QUIET.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if thread::current().name() != Some(EXAMPLE_THREAD) {
+                default(info);
+            }
+        }));
+    });
+
+    let markdown = markdown.to_owned();
+    thread::Builder::new()
+        .name(EXAMPLE_THREAD.to_owned())
+        .spawn(move || -> Result<String, String> {
+            let mut ids = ids::IdPool::new(0);
+            // a section page, examples have no frontmatter and articles need a title and date
+            let mut builder =
+                content::PageBuilder::new(ids.next(), unsafe { files::FilePath::new("_index.md") });
+            super::Loader::default()
+                .load(Box::new(std::io::Cursor::new(markdown)), &mut builder)
+                .map_err(|e| e.to_string())?;
+            let page = builder.build().map_err(|e| e.to_string())?;
+            let html = render::render_page(&page.content, &render::NullHighligher, false);
+            Ok(html
+                .trim()
+                .trim_start_matches("<article>")
+                .trim_end_matches("</article>")
+                .to_owned())
+        })
+        .unwrap()
+        .join()
+        .unwrap_or_else(|_| Err("panicked".to_owned()))
+}
+
+// `<examples> <differs|error>: <reason>`, where examples are numbers or ranges separated by commas
+fn parse_failing_list(list: &str) -> Vec<Line> {
+    let mut lines = vec![];
+    for line in list.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            lines.push(Line::Comment(line.to_owned()));
+            continue;
+        }
+        let (head, reason) = line
+            .split_once(':')
+            .unwrap_or_else(|| panic!("{line:?} doesn't say why the examples fail"));
+        let (examples, failure) = head.trim().rsplit_once(' ').unwrap();
+        let failure = match failure {
+            "differs" => Failure::Differs,
+            "error" => Failure::Error,
+            other => panic!("{other:?} isn't a way an example can fail"),
+        };
+        let mut set = BTreeSet::new();
+        for part in examples.split(',').map(str::trim) {
+            match part.split_once('-') {
+                None => {
+                    set.insert(part.parse().unwrap());
+                }
+                Some((from, to)) => {
+                    set.extend(from.trim().parse::<usize>().unwrap()..=to.trim().parse().unwrap())
+                }
+            }
+        }
+        lines.push(Line::Known {
+            examples: set,
+            failure,
+            reason: reason.trim().to_owned(),
+        });
+    }
+    lines
+}
+
+fn format_failing_list(lines: &[Line]) -> String {
+    let mut list = String::new();
+    for line in lines.iter() {
+        match line {
+            Line::Comment(comment) => list.push_str(comment),
+            Line::Known { examples, .. } if examples.is_empty() => continue,
+            Line::Known {
+                examples,
+                failure,
+                reason,
+            } => {
+                let mut parts = vec![];
+                let mut iter = examples.iter().copied().peekable();
+                while let Some(from) = iter.next() {
+                    let mut to = from;
+                    while iter.peek() == Some(&(to + 1)) {
+                        to = iter.next().unwrap();
+                    }
+                    if from == to {
+                        parts.push(format!("{from}"));
+                    } else {
+                        parts.push(format!("{from}-{to}"));
+                    }
+                }
+                list.push_str(&format!("{} {failure}: {reason}", parts.join(", ")));
+            }
+        }
+        list.push('\n');
+    }
+    list
+}
+
+// whitespace next to block level tags, attribute order and quoting, self closing slashes and the
+// choice of character references don't matter, everything else has to match
+fn normalize(html: &str) -> String {
+    let tokens = tokenize(html);
+    let mut out = String::new();
+    let mut pre = 0usize;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Tag {
+                name,
+                closing,
+                html,
+            } => {
+                if name == "pre" {
+                    pre = if *closing {
+                        pre.saturating_sub(1)
+                    } else {
+                        pre + 1
+                    };
+                }
+                out.push_str(html);
+            }
+            Token::Text(text) if pre > 0 => out.push_str(&render::escape(unescape(text))),
+            Token::Text(text) => {
+                let mut text = collapse(&render::escape(unescape(text)));
+                if idx == 0 || tokens[idx - 1].is_block() {
+                    text = text.trim_start().to_owned();
+                }
+                if tokens.get(idx + 1).map(Token::is_block).unwrap_or(true) {
+                    text = text.trim_end().to_owned();
+                }
+                out.push_str(&text);
+            }
+        }
+    }
+    out
+}
+
+// runs of whitespace become a single space
+fn collapse(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space {
+                out.push(' ');
+                space = false;
+            }
+            out.push(c);
+        }
+    }
+    if space {
+        out.push(' ');
+    }
+    out
+}
+
+const BLOCK_TAGS: &[&str] = &[
+    "blockquote",
+    "br",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+enum Token {
+    Tag {
+        name: String,
+        closing: bool,
+        html: String,
+    },
+    Text(String),
+}
+
+impl Token {
+    fn is_block(&self) -> bool {
+        matches!(self, Token::Tag { name, .. } if BLOCK_TAGS.contains(&name.as_str()))
+    }
+}
+
+fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match tag_end(rest) {
+            None => {
+                text.push('<');
+                rest = &rest[1..];
+            }
+            Some(end) => {
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(tag(&rest[..end]));
+                rest = &rest[end..];
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    tokens
+}
+
+// where the tag starting the input ends, if it is one
+fn tag_end(html: &str) -> Option<usize> {
+    let next = html[1..].chars().next()?;
+    if html.starts_with("<!--") {
+        return html.find("-->").map(|end| end + 3);
+    }
+    if !(next.is_ascii_alphabetic() || matches!(next, '/' | '!' | '?')) {
+        return None;
+    }
+    let mut quote = None;
+    for (idx, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(idx + 1),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn tag(html: &str) -> Token {
+    if html.starts_with("<!") || html.starts_with("<?") {
+        return Token::Tag {
+            name: "!".to_owned(),
+            closing: false,
+            html: html.to_owned(),
+        };
+    }
+
+    let inner = html[1..html.len() - 1].trim();
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    let inner = inner.trim_end_matches('/');
+    let name_end = inner
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(inner.len());
+    let name = inner[..name_end].to_ascii_lowercase();
+
+    let mut attrs = vec![];
+    let mut rest = inner[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+        let value = match rest.strip_prefix('=') {
+            None => String::new(),
+            Some(value) => {
+                let value = value.trim_start();
+                let (v, remaining) = match value.chars().next() {
+                    Some(q @ ('"' | '\'')) => {
+                        let end = value[1..].find(q).map(|e| e + 1).unwrap_or(value.len());
+                        (&value[1..end], value.get(end + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+                rest = remaining;
+                unescape(v)
+            }
+        };
+        attrs.push((key, value));
+        rest = rest.trim_start();
+    }
+    attrs.sort();
+
+    let mut out = format!("<{}{}", if closing { "/" } else { "" }, name);
+    for (key, value) in attrs {
+        out.push_str(&format!(" {}=\"{}\"", key, render::escape(value)));
+    }
+    out.push('>');
+    Token::Tag {
+        name,
+        closing,
+        html: out,
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
@@ -4,25 +4,52 @@ use super::Error;
 
 use crate::{
     content::{self, doctree, Metadata},
-    md::frontmatter::frontmatter_to_page_meta,
+    md::code::{parse_meta, Include},
+    md::frontmatter::{frontmatter_to_page_meta, required_page_meta, GenericError},
+    md::html::HtmlPolicy,
+    site::cache::checksum,
+    slug::Slugs,
 };
 
-fn slug<S: AsRef<str>>(s: S) -> String {
-    s.as_ref().replace(' ', "-").to_lowercase()
+// the text of the nodes with any markup and raw html left out
+fn plain_text(nodes: &[mdast::Node]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            mdast::Node::Html(_) => String::new(),
+            node => match node.children() {
+                Some(children) => plain_text(children),
+                None => node.to_string(),
+            },
+        })
+        .collect()
 }
 
 pub struct MarkdownPageBuilder<'p> {
     groups: Vec<doctree::Group>,
     builder: &'p mut content::PageBuilder,
     opts: &'p markdown::ParseOptions,
+    html: HtmlPolicy,
+    // frontmatter dates without an offset are in this timezone
+    timezone: chrono_tz::Tz,
+    // heading ids have to be unique within the page
+    ids: Slugs,
 }
 
 impl<'p> MarkdownPageBuilder<'p> {
-    pub fn new(builder: &'p mut content::PageBuilder, opts: &'p markdown::ParseOptions) -> Self {
+    pub fn new(
+        builder: &'p mut content::PageBuilder,
+        opts: &'p markdown::ParseOptions,
+        html: HtmlPolicy,
+        timezone: chrono_tz::Tz,
+    ) -> Self {
         Self {
             groups: Default::default(),
             builder,
             opts,
+            html,
+            timezone,
+            ids: Default::default(),
         }
     }
 
@@ -35,9 +62,9 @@ impl<'p> MarkdownPageBuilder<'p> {
                     panic!("Unpopped groups exist");
                 }
                 self.builder.content(grp.into());
-                Ok(())
+                required_page_meta(self.builder, self.timezone)
             }
-            any @ _ => Error::Unexpected(format!("Expected Root, got {:?}", any)).into(),
+            any => Error::Unexpected(format!("Expected Root, got {:?}", any)).into(),
         }
     }
 
@@ -93,6 +120,7 @@ impl<'p> MarkdownPageBuilder<'p> {
             Node::FootnoteDefinition(def) => self.footnote_definition(def),
             Node::FootnoteReference(ftn) => self.footnote_reference(ftn),
             Node::Heading(heading) => self.header(heading),
+            Node::Html(html) => self.html(html),
             Node::Image(img) => self.image(img),
             Node::ImageReference(img) => self.image_reference(img),
             Node::InlineCode(code) => self.inline_code(code),
@@ -103,11 +131,17 @@ impl<'p> MarkdownPageBuilder<'p> {
             Node::Strong(str_) => self.strong(str_),
             Node::Table(tbl) => self.table(tbl),
             Node::Text(txt) => self.text(txt),
-            Node::Break(_) | Node::ThematicBreak(_) => Ok(()),
-            any @ _ => Error::Unexpected(format!("Unexpected element: {:?}", any)).into(),
+            Node::Break(_) => self.break_(doctree::Element::LineBreak),
+            Node::ThematicBreak(_) => self.break_(doctree::Element::Rule),
+            any => Error::Unexpected(format!("Unexpected element: {:?}", any)).into(),
         }
     }
 
+    fn break_(&mut self, elm: doctree::Element) -> crate::Result<()> {
+        self.push_element(elm);
+        Ok(())
+    }
+
     fn handle_meta(&mut self, meta: &mdast::Yaml) -> crate::Result<()> {
         frontmatter_to_page_meta(meta, self.builder)?;
 
@@ -115,7 +149,7 @@ impl<'p> MarkdownPageBuilder<'p> {
             let node = markdown::to_mdast(&s, self.opts).map_err(|e| Error::ParseError(e))?;
             self.builder.summary = Some(match node {
                 mdast::Node::Root(r) => self.collect_children(&r.children)?,
-                node @ _ => {
+                node => {
                     self.push_group();
                     self.walk(&node)?;
                     self.pop_group()
@@ -126,10 +160,52 @@ impl<'p> MarkdownPageBuilder<'p> {
     }
 
     fn codeblock(&mut self, code: &markdown::mdast::Code) -> crate::Result<()> {
+        let (options, include) = parse_meta(code.meta.as_deref().unwrap_or("")).map_err(|e| {
+            GenericError::with_reason(e, format!("on page {}", self.builder.filepath))
+        })?;
+
         // TODO get rid of clones -- mem::replace would be nice if possible
-        let content = doctree::CodeLiteral::from(code.value.clone());
+        let content = match include {
+            None => code.value.clone(),
+            Some(include) => self.include(&include)?,
+        };
         let lang = code.lang.clone().map(|l| doctree::CodeLanguage::from(l));
-        self.push_element(doctree::Code::new(content, lang).block());
+        self.push_element(
+            doctree::Code::new(doctree::CodeLiteral::from(content), lang)
+                .with_options(options)
+                .block(),
+        );
+        Ok(())
+    }
+
+    // included files are relative to the page, they're tracked so the page is rebuilt when they
+    // change
+    fn include(&mut self, include: &Include) -> crate::Result<String> {
+        let reason = || {
+            format!(
+                "including {} on page {}",
+                include.path.display(),
+                self.builder.filepath
+            )
+        };
+        let path = match self.builder.filepath.parent() {
+            None => include.path.clone(),
+            Some(dir) => dir.join(&include.path),
+        };
+        let source =
+            std::fs::read_to_string(&path).map_err(|e| GenericError::with_reason(e, reason()))?;
+        let selected = include
+            .select(&source)
+            .map_err(|e| GenericError::with_reason(e, reason()))?;
+        self.builder.depends_on(path, checksum(source.as_bytes()));
+        Ok(selected)
+    }
+
+    fn html(&mut self, html: &markdown::mdast::Html) -> crate::Result<()> {
+        let html = self.html.apply(&html.value).map_err(|e| {
+            GenericError::with_reason(e, format!("on page {}", self.builder.filepath))
+        })?;
+        self.push_element(doctree::Element::RawHtml(html));
         Ok(())
     }
 
@@ -176,16 +252,16 @@ impl<'p> MarkdownPageBuilder<'p> {
     }
 
     fn header(&mut self, header: &markdown::mdast::Heading) -> crate::Result<()> {
-        match doctree::Element::from(self.collect_children(&header.children)?) {
-            doctree::Element::Text(txt) => {
-                let display = txt.inner();
-                let id = slug(&display);
-                let header = doctree::Header::create(header.depth, display, id);
-                self.push_element(doctree::Element::Heading(header));
-                Ok(())
-            }
-            _ => Err(todo!()),
-        }
+        let content = self.collect_children(&header.children)?;
+        let display = plain_text(&header.children);
+        let id = self.ids.unique(&display);
+        self.push_element(doctree::Element::Heading(doctree::Header::create(
+            header.depth,
+            content,
+            display,
+            id,
+        )));
+        Ok(())
     }
 
     fn image(&mut self, img: &markdown::mdast::Image) -> crate::Result<()> {
@@ -217,7 +293,7 @@ impl<'p> MarkdownPageBuilder<'p> {
         let label = get_hex_hash_string(&link.url);
         let content = self.collect_children(&link.children)?;
         self.push_element(doctree::Element::HrefReference(
-            doctree::HrefReference::create(label.clone(), content.into()),
+            doctree::HrefReference::create(label.clone(), content),
         ));
         self.push_href(label.clone(), link.url.clone());
         Ok(())
@@ -228,23 +304,28 @@ impl<'p> MarkdownPageBuilder<'p> {
             .hrefs(|hrefs| hrefs.add_label(&link.identifier));
         let content = self.collect_children(&link.children)?;
         self.push_element(doctree::Element::HrefReference(
-            doctree::HrefReference::create(link.identifier.clone(), content.into()),
+            doctree::HrefReference::create(link.identifier.clone(), content),
         ));
         Ok(())
     }
 
     fn list(&mut self, list: &markdown::mdast::List) -> crate::Result<()> {
         use markdown::mdast::Node;
-        let mut lst = doctree::List::default();
+        let mut lst = match (list.ordered, list.start) {
+            (true, start) => doctree::List::ordered(start.unwrap_or(1)),
+            (false, _) => doctree::List::default(),
+        };
+        lst.spread(list.spread);
         for item in list.children.iter() {
             match item {
                 Node::ListItem(it) => {
                     let content = self.collect_children(&it.children)?;
-                    lst.push(doctree::ListItem::from(content));
-                }
-                any @ _ => {
-                    return Error::Unexpected(format!("Unexpected element: {:?}", any)).into()
+                    lst.push(match it.checked {
+                        None => doctree::ListItem::from(content),
+                        Some(checked) => doctree::ListItem::task(content, checked),
+                    });
                 }
+                any => return Error::Unexpected(format!("Unexpected element: {:?}", any)).into(),
             }
         }
         self.push_element(doctree::Element::List(lst));
@@ -264,16 +345,26 @@ impl<'p> MarkdownPageBuilder<'p> {
     }
 
     fn table(&mut self, md_table: &markdown::mdast::Table) -> crate::Result<()> {
+        use markdown::mdast::AlignKind;
         use markdown::mdast::Node;
-        let mut table = doctree::Table::new();
+        let mut table = doctree::Table::aligned(
+            md_table
+                .align
+                .iter()
+                .map(|a| match a {
+                    AlignKind::Left => doctree::Alignment::Left,
+                    AlignKind::Right => doctree::Alignment::Right,
+                    AlignKind::Center => doctree::Alignment::Center,
+                    AlignKind::None => doctree::Alignment::None,
+                })
+                .collect(),
+        );
         for node in md_table.children.iter() {
             match node {
                 Node::TableRow(row) => {
                     self.table_row(&mut table, row)?;
                 }
-                any @ _ => {
-                    return Error::Unexpected(format!("Unexpected element: {:?}", any)).into()
-                }
+                any => return Error::Unexpected(format!("Unexpected element: {:?}", any)).into(),
             }
         }
         self.push_element(doctree::Element::Table(table));
@@ -292,9 +383,7 @@ impl<'p> MarkdownPageBuilder<'p> {
                 Node::TableCell(cell) => {
                     self.table_cell(&mut row, cell)?;
                 }
-                any @ _ => {
-                    return Error::Unexpected(format!("Unexpected element: {:?}", any)).into()
-                }
+                any => return Error::Unexpected(format!("Unexpected element: {:?}", any)).into(),
             }
         }
         tbl.push(row);
@@ -307,7 +396,7 @@ impl<'p> MarkdownPageBuilder<'p> {
         cell: &markdown::mdast::TableCell,
     ) -> crate::Result<()> {
         let children = self.collect_children(&cell.children)?;
-        row.push(doctree::TableCell::create(children.into()));
+        row.push(doctree::TableCell::create(children));
         Ok(())
     }
 
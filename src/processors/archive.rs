@@ -1,11 +1,9 @@
-use crate::{content::CorpusEntry, ids, jinja};
-use std::{
-    collections::{
-        hash_map::{Entry, OccupiedEntry, VacantEntry},
-        HashMap,
-    },
-    hash::Hash,
-};
+use crate::{config, content::CorpusEntry, ids, jinja, slug};
+
+use super::paginate::Pagination;
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap, hash::Hash};
+
+use chrono::NaiveDate;
 
 use crate::{
     content::{self, Metadata},
@@ -30,62 +28,145 @@ impl<K, V> Buckets<K, V>
 where
     K: Eq + Hash,
 {
-    pub fn with_capacity(cap: usize) -> Self {
-        Self(HashMap::with_capacity(cap))
-    }
-
     pub fn push(&mut self, key: K, value: V) {
-        self.0.entry(key).or_insert_with(Vec::new).push(value);
-    }
-
-    pub fn insert(&mut self, key: K, mut value: Vec<V>) {
-        match self.0.entry(key) {
-            Entry::Vacant(v) => {
-                v.insert(value);
-            }
-            Entry::Occupied(o) => {
-                o.into_mut().append(&mut value);
-            }
-        }
+        self.0.entry(key).or_default().push(value);
     }
 
     pub fn buckets(&self) -> impl Iterator<Item = (&K, &Vec<V>)> {
         self.0.iter()
     }
-
-    pub fn into_buckets(self) -> impl Iterator<Item = (K, Vec<V>)> {
-        self.0.into_iter()
-    }
-
-    pub fn len(&self) -> usize {
-        self.0.len()
-    }
 }
 
 #[derive(Debug, serde::Serialize)]
 pub struct ArchiveEntry<'a> {
     summary: Option<&'a str>,
     title: &'a str,
+    url: String,
+    date: Option<content::Date>,
+    tags: Vec<&'a str>,
 }
 
-impl<'a> From<&'a site::RenderedPage<'a>> for ArchiveEntry<'a> {
-    fn from(page: &'a site::RenderedPage<'a>) -> Self {
+impl<'a> ArchiveEntry<'a> {
+    pub(crate) fn new(
+        base: &url::Url,
+        rendered: &'a site::RenderedPageMetadata<'a>,
+        page: &'a content::Page,
+        tag_key: &str,
+    ) -> Self {
         Self {
-            title: &page.metadata().title,
-            summary: page.metadata().summary.as_deref(),
+            title: &rendered.title,
+            summary: rendered.summary.as_deref(),
+            url: site::href(base, &rendered.url.to_string()),
+            date: page.meta.when,
+            tags: terms(page, tag_key).collect(),
+        }
+    }
+}
+
+// the strings listed under `key` in a page's frontmatter, a lone string counts as one term
+pub fn terms<'p>(page: &'p content::Page, key: &str) -> impl Iterator<Item = &'p str> {
+    let terms = match page.meta.meta.get(key) {
+        Some(Metadata::List(terms)) => terms.as_slice(),
+        Some(term @ Metadata::Str(_)) => std::slice::from_ref(term),
+        _ => &[],
+    };
+    terms.iter().filter_map(|t| match t {
+        Metadata::Str(s) => Some(s.as_str()),
+        _ => None,
+    })
+}
+
+// a group of pages as archivists see it when ordering and naming groups
+pub struct Bucket<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) pages: Vec<&'a content::Page>,
+}
+
+// one group of pages as templates see it, url is only set when the group has a page of its own
+#[derive(Debug, serde::Serialize)]
+struct ArchiveBucket<'a> {
+    name: &'a str,
+    title: &'a str,
+    url: Option<&'a str>,
+    count: usize,
+    pages: &'a [minijinja::Value],
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+pub enum Sorting {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl Sorting {
+    pub fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            Sorting::Ascending => ordering,
+            Sorting::Descending => ordering.reverse(),
         }
     }
 }
 
-pub struct DateArchivist<'a>(pub &'a str);
-pub enum TagSorting {
+#[derive(Debug, Clone, Copy, Default)]
+pub enum DateGrouping {
+    Year,
+    #[default]
+    Month,
+    Day,
+}
+
+impl DateGrouping {
+    // bucket names double as the path of the bucket's page so they're built from path segments
+    fn pattern(self) -> &'static str {
+        match self {
+            DateGrouping::Year => "%Y",
+            DateGrouping::Month => "%Y/%m",
+            DateGrouping::Day => "%Y/%m/%d",
+        }
+    }
+}
+
+pub struct DateArchivist<'a> {
+    localization: &'a config::Localization,
+    grouping: DateGrouping,
+    sorting: Sorting,
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+pub enum TermOrdering {
+    #[default]
     Alphabetical,
+    // how many pages carry the term
+    Count,
+    // when the term was last used
+    Recent,
+}
+
+// buckets pages by the terms listed under a frontmatter key, e.g. tags or categories
+pub struct TermArchivist<'a> {
+    key: &'a str,
+    ordering: TermOrdering,
+    sorting: Sorting,
+    // where each term's page goes, e.g. "tags/{slug}"
+    pattern: &'a str,
 }
-pub struct TagArchivist(pub TagSorting);
 
 impl<'a> DateArchivist<'a> {
-    fn format_date<S: AsRef<str>>(&self, written: S) -> String {
-        written.as_ref().to_owned()
+    pub fn new(
+        localization: &'a config::Localization,
+        grouping: DateGrouping,
+        sorting: Sorting,
+    ) -> Self {
+        Self {
+            localization,
+            grouping,
+            sorting,
+        }
+    }
+
+    fn format_date(&self, written: &content::Date) -> String {
+        written.format(self.grouping.pattern(), self.localization.timezone)
     }
 }
 
@@ -101,25 +182,105 @@ impl<'a> Archivist for DateArchivist<'a> {
 
         Ok(())
     }
+
+    // zero padded so comparing the names is chronological
+    fn compare_buckets(&self, a: &Bucket, b: &Bucket) -> Ordering {
+        self.sorting.apply(a.name.cmp(b.name))
+    }
+
+    fn compare_pages(&self, a: &content::Page, b: &content::Page) -> Ordering {
+        self.sorting.apply(a.meta.when.cmp(&b.meta.when))
+    }
+
+    fn bucket_title(&self, bucket: &Bucket) -> String {
+        let mut parts = bucket.name.split('/').map(|p| p.parse::<u32>().ok());
+        let (year, month, day) = (
+            parts.next().flatten().unwrap_or(1970),
+            parts.next().flatten().unwrap_or(1),
+            parts.next().flatten().unwrap_or(1),
+        );
+        let date = match NaiveDate::from_ymd_opt(year as i32, month, day) {
+            None => return bucket.name.to_owned(),
+            Some(d) => d,
+        };
+        match self.grouping {
+            DateGrouping::Year => date.format("%Y"),
+            DateGrouping::Month => date.format("%B %Y"),
+            DateGrouping::Day => date.format("%B %-d, %Y"),
+        }
+        .to_string()
+    }
 }
 
-impl Archivist for TagArchivist {
+impl<'a> TermArchivist<'a> {
+    pub fn new(key: &'a str, ordering: TermOrdering, sorting: Sorting, pattern: &'a str) -> Self {
+        Self {
+            key,
+            ordering,
+            sorting,
+            pattern,
+        }
+    }
+}
+
+impl<'a> Archivist for TermArchivist<'a> {
     fn archive_page(
         &mut self,
         page: &content::Page,
         buckets: &mut Buckets<String, ids::Id<content::CorpusEntry>>,
     ) -> crate::Result<()> {
-        if let Some(Metadata::List(tags)) = page.meta.meta.get("tags") {
-            for tag in tags.iter().filter_map(|t| match t {
-                Metadata::Str(s) => Some(s.to_lowercase()),
-                _ => None,
-            }) {
-                buckets.push(tag, page.id.clone());
+        // "Rust" and "rust" are the same term
+        let mut seen = Vec::new();
+        for term in terms(page, self.key).map(slug::slugify) {
+            if !term.is_empty() && !seen.contains(&term) {
+                buckets.push(term.clone(), page.id.clone());
+                seen.push(term);
             }
         }
 
         Ok(())
     }
+
+    // ties are always broken alphabetically so the order is stable between builds
+    fn compare_buckets(&self, a: &Bucket, b: &Bucket) -> Ordering {
+        let latest = |b: &Bucket| b.pages.iter().filter_map(|p| p.meta.when).max();
+        let ordering = match self.ordering {
+            TermOrdering::Alphabetical => a.name.cmp(b.name),
+            TermOrdering::Count => a.pages.len().cmp(&b.pages.len()),
+            TermOrdering::Recent => latest(a).cmp(&latest(b)),
+        };
+        self.sorting
+            .apply(ordering)
+            .then_with(|| a.name.cmp(b.name))
+    }
+
+    // most recent first
+    fn compare_pages(&self, a: &content::Page, b: &content::Page) -> Ordering {
+        b.meta
+            .when
+            .cmp(&a.meta.when)
+            .then_with(|| a.meta.title.cmp(&b.meta.title))
+    }
+
+    // the term as it was first written rather than its slug
+    fn bucket_title(&self, bucket: &Bucket) -> String {
+        bucket
+            .pages
+            .iter()
+            .flat_map(|p| terms(p, self.key))
+            .find(|t| slug::slugify(t) == bucket.name)
+            .unwrap_or(bucket.name)
+            .to_owned()
+    }
+
+    fn bucket_url(&self, bucket: &str) -> files::FilePath {
+        unsafe {
+            files::FilePath::new(format!(
+                "{}/index.html",
+                self.pattern.replace("{slug}", bucket).trim_matches('/')
+            ))
+        }
+    }
 }
 
 pub struct Archive<'a, A>
@@ -129,14 +290,36 @@ where
     buckets: Buckets<String, ids::Id<content::CorpusEntry>>,
     archivist: A,
     template: PageTemplate<'a>,
+    bucket_template: Option<&'a str>,
+    pagination: Option<Pagination>,
+    // the frontmatter key entries take their tags from
+    tag_key: &'a str,
 }
 
-pub trait Archivist {
+pub trait Archivist: Send + Sync {
     fn archive_page(
         &mut self,
         page: &content::Page,
         buckets: &mut Buckets<String, ids::Id<content::CorpusEntry>>,
     ) -> crate::Result<()>;
+
+    fn compare_buckets(&self, a: &Bucket, b: &Bucket) -> Ordering {
+        a.name.cmp(b.name)
+    }
+
+    fn compare_pages(&self, a: &content::Page, b: &content::Page) -> Ordering {
+        a.meta.title.cmp(&b.meta.title)
+    }
+
+    // how the bucket is introduced on pages
+    fn bucket_title(&self, bucket: &Bucket) -> String {
+        bucket.name.to_owned()
+    }
+
+    // where the bucket's own page goes, if buckets get one
+    fn bucket_url(&self, bucket: &str) -> files::FilePath {
+        unsafe { files::FilePath::new(format!("{}/index.html", bucket)) }
+    }
 }
 
 impl<'a, A> Archive<'a, A>
@@ -148,8 +331,29 @@ where
             archivist,
             template: metadata,
             buckets: Default::default(),
+            bucket_template: None,
+            pagination: None,
+            tag_key: "tags",
         }
     }
+
+    // entries list the terms under `key` as their tags, for sites that re-keyed the tags taxonomy
+    pub fn with_tag_key(mut self, key: &'a str) -> Self {
+        self.tag_key = key;
+        self
+    }
+
+    // every bucket also gets a page of its own, rendered with `template` if the site has it
+    pub fn with_bucket_pages(mut self, template: &'a str) -> Self {
+        self.bucket_template = Some(template);
+        self
+    }
+
+    // bucket pages list at most `per_page` pages, the rest spill onto page/2/index.html etc
+    pub fn paginate(mut self, per_page: Option<usize>) -> Self {
+        self.pagination = per_page.map(Pagination::new);
+        self
+    }
 }
 
 impl<'archive, A> site::Processor for Archive<'archive, A>
@@ -166,28 +370,80 @@ where
 
     fn site_rendering<'site>(
         &self,
-        _: &'site content::Corpus,
+        corpus: &'site content::Corpus,
         site: &mut site::RenderingSite<'_, 'site, '_>,
     ) -> crate::Result<()> {
-        let mut page = site.page(&self.template.template);
-        let mut buckets = Buckets::with_capacity(self.buckets.len());
-
-        for (k, v) in self.buckets.buckets() {
-            buckets.insert(
-                k.as_str(),
-                v.iter()
-                    .filter_map(|o| match site.get_by_origin(o) {
-                        Some(site::Writable::Page(page)) => Some(ArchiveEntry {
-                            title: &page.metadata().title,
-                            summary: page.metadata().summary.as_deref(),
-                        }),
-                        _ => None,
-                    })
-                    .collect(),
-            );
+        let mut buckets: Vec<Bucket> = self
+            .buckets
+            .buckets()
+            .map(|(name, ids)| {
+                let mut pages: Vec<&content::Page> =
+                    ids.iter().filter_map(|id| corpus.page(id)).collect();
+                pages.sort_by(|a, b| self.archivist.compare_pages(a, b));
+                Bucket { name, pages }
+            })
+            .collect();
+        buckets.sort_by(|a, b| self.archivist.compare_buckets(a, b));
+
+        // sites from before buckets had pages of their own won't have the template for them
+        let bucket_template = self
+            .bucket_template
+            .filter(|t| site.renderer().has_template(t));
+
+        let mut archives = Vec::with_capacity(buckets.len());
+        for bucket in buckets.iter() {
+            let url = bucket_template.map(|_| self.archivist.bucket_url(bucket.name));
+            let title = self.archivist.bucket_title(bucket);
+            let href = url
+                .as_ref()
+                .map(|u| site::href(site.base_url(), &u.to_string()));
+            let entries: Vec<minijinja::Value> = bucket
+                .pages
+                .iter()
+                .filter_map(|p| {
+                    let rendered = site.get_by_origin(&p.id)?;
+                    Some(minijinja::Value::from_serialize(ArchiveEntry::new(
+                        site.base_url(),
+                        rendered,
+                        p,
+                        self.tag_key,
+                    )))
+                })
+                .collect();
+            let listing = |pages| {
+                minijinja::Value::from_serialize(ArchiveBucket {
+                    name: bucket.name,
+                    title: &title,
+                    url: href.as_deref(),
+                    count: entries.len(),
+                    pages,
+                })
+            };
+            let value = listing(&entries);
+
+            if let (Some(template), Some(url)) = (bucket_template, &url) {
+                let pagination = self
+                    .pagination
+                    .unwrap_or_else(|| Pagination::new(entries.len()));
+                for (url, paginator, pages) in pagination.pages(site.base_url(), url, &entries) {
+                    let mut page = site.page(template);
+                    page.values().merge(minijinja::context! {
+                        bucket => listing(pages),
+                        paginator => minijinja::Value::from_serialize(&paginator),
+                    });
+                    let meta = RenderedPageMetadata {
+                        origin: None,
+                        title: Cow::Owned(title.clone()),
+                        url: Cow::Owned(url),
+                        summary: None,
+                    };
+                    site.render_page(meta, page)?;
+                }
+            }
+            archives.push(value);
         }
 
-        let archives = Vec::from_iter(buckets.into_buckets());
+        let mut page = site.page(self.template.template);
         page.values()
             .merge(minijinja::context! { archive =>  archives });
         let meta = self.template.stamp();
@@ -195,4 +451,9 @@ where
 
         Ok(())
     }
+
+    fn finalize(&mut self) -> crate::Result<()> {
+        self.buckets = Default::default();
+        Ok(())
+    }
 }
@@ -13,14 +13,16 @@ impl site::Processor for Cleaner {
     {
         use files::Path::*;
 
+        // nothing to clean up the first time around
         match &self.0 {
-            File(f) => {
+            File(f) if f.exists() => {
                 fs::remove_file(f)?;
             }
-            Dir(d) => {
+            Dir(d) if d.as_ref().exists() => {
                 fs::remove_dir_all(d)?;
                 fs::create_dir_all(d)?;
             }
+            _ => {}
         };
 
         Ok(())
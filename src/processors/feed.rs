@@ -0,0 +1,480 @@
+use std::collections::{BTreeMap, HashSet};
+
+use url::Url;
+
+use crate::{
+    config,
+    content::{self, PageKind},
+    files,
+    render::{self, CodeHighlighter},
+    site, slug,
+};
+
+use super::archive::terms;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+    // https://www.jsonfeed.org/version/1.1/
+    Json,
+}
+
+impl FeedFormat {
+    fn file(self) -> &'static str {
+        match self {
+            FeedFormat::Atom => "atom.xml",
+            FeedFormat::Rss => "rss.xml",
+            FeedFormat::Json => "feed.json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum FeedContent {
+    // only the part of the page before its summary break, nothing for pages without one
+    #[default]
+    Summary,
+    Full,
+}
+
+// the site's newest articles as feeds at the site root, and per term of every taxonomy
+// next to the term's page when `terms` is set
+pub struct Feeds<'a> {
+    options: config::Feeds,
+    base: &'a Url,
+    formats: Vec<FeedFormat>,
+    taxonomies: &'a [config::Taxonomy],
+    highlighter: Box<dyn CodeHighlighter>,
+}
+
+struct FeedEntry<'p> {
+    page: &'p content::Page,
+    title: String,
+    url: String,
+    date: content::Date,
+    summary: Option<String>,
+    content: Option<String>,
+}
+
+struct Feed<'e, 'p> {
+    title: String,
+    // where the feed points readers, e.g. the site or a tag's page
+    home: String,
+    // where the feed files go, empty for the site root
+    dir: String,
+    entries: Vec<&'e FeedEntry<'p>>,
+}
+
+impl<'a> Feeds<'a> {
+    pub fn new(
+        options: config::Feeds,
+        base: &'a Url,
+        formats: Vec<FeedFormat>,
+        taxonomies: &'a [config::Taxonomy],
+        highlighter: Box<dyn CodeHighlighter>,
+    ) -> Self {
+        Self {
+            options,
+            base,
+            formats,
+            taxonomies,
+            highlighter,
+        }
+    }
+
+    // categories come from whatever key the tags taxonomy reads
+    fn tag_key(&self) -> &str {
+        config::tag_key(self.taxonomies)
+    }
+
+    fn title(&self) -> &str {
+        match &self.options.title {
+            Some(title) => title,
+            None => self.base.host_str().unwrap_or_default(),
+        }
+    }
+
+    fn write(&self, feed: &Feed, format: FeedFormat, url: &str) -> String {
+        match format {
+            FeedFormat::Atom => self.atom(feed, url),
+            FeedFormat::Rss => self.rss(feed, url),
+            FeedFormat::Json => self.json(feed, url),
+        }
+    }
+
+    fn atom(&self, feed: &Feed, url: &str) -> String {
+        // an empty feed was last updated by this build
+        let updated = feed
+            .entries
+            .iter()
+            .map(|e| e.date)
+            .max()
+            .unwrap_or_else(content::Date::now);
+        let author = self.options.author.as_deref().unwrap_or(self.title());
+
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        out.push_str(&format!(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:base=\"{}\">\n",
+            xml(self.base.as_str())
+        ));
+        out.push_str(&format!("  <title>{}</title>\n", xml(&feed.title)));
+        out.push_str(&format!("  <id>{}</id>\n", xml(url)));
+        out.push_str(&format!(
+            "  <link rel=\"self\" type=\"application/atom+xml\" href=\"{}\" />\n",
+            xml(url)
+        ));
+        out.push_str(&format!(
+            "  <link rel=\"alternate\" type=\"text/html\" href=\"{}\" />\n",
+            xml(&feed.home)
+        ));
+        out.push_str(&format!("  <updated>{}</updated>\n", updated));
+        out.push_str(&format!(
+            "  <author><name>{}</name></author>\n",
+            xml(author)
+        ));
+        for entry in feed.entries.iter() {
+            out.push_str("  <entry>\n");
+            out.push_str(&format!("    <title>{}</title>\n", xml(&entry.title)));
+            out.push_str(&format!("    <id>{}</id>\n", xml(&entry.url)));
+            out.push_str(&format!(
+                "    <link rel=\"alternate\" type=\"text/html\" href=\"{}\" />\n",
+                xml(&entry.url)
+            ));
+            out.push_str(&format!("    <published>{}</published>\n", entry.date));
+            out.push_str(&format!("    <updated>{}</updated>\n", entry.date));
+            for tag in terms(entry.page, self.tag_key()) {
+                out.push_str(&format!("    <category term=\"{}\" />\n", xml(tag)));
+            }
+            if let Some(summary) = &entry.summary {
+                out.push_str(&format!(
+                    "    <summary type=\"html\">{}</summary>\n",
+                    xml(summary)
+                ));
+            }
+            if let Some(content) = &entry.content {
+                out.push_str(&format!(
+                    "    <content type=\"html\">{}</content>\n",
+                    xml(content)
+                ));
+            }
+            out.push_str("  </entry>\n");
+        }
+        out.push_str("</feed>\n");
+        out
+    }
+
+    fn rss(&self, feed: &Feed, url: &str) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        out.push_str(
+            "<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n  <channel>\n",
+        );
+        out.push_str(&format!("    <title>{}</title>\n", xml(&feed.title)));
+        out.push_str(&format!("    <link>{}</link>\n", xml(&feed.home)));
+        out.push_str(&format!(
+            "    <description>{}</description>\n",
+            xml(&feed.title)
+        ));
+        out.push_str(&format!(
+            "    <atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\" />\n",
+            xml(url)
+        ));
+        if let Some(updated) = feed.entries.iter().map(|e| e.date).max() {
+            out.push_str(&format!(
+                "    <lastBuildDate>{}</lastBuildDate>\n",
+                updated.to_rfc2822()
+            ));
+        }
+        for entry in feed.entries.iter() {
+            out.push_str("    <item>\n");
+            out.push_str(&format!("      <title>{}</title>\n", xml(&entry.title)));
+            out.push_str(&format!("      <link>{}</link>\n", xml(&entry.url)));
+            out.push_str(&format!(
+                "      <guid isPermaLink=\"true\">{}</guid>\n",
+                xml(&entry.url)
+            ));
+            out.push_str(&format!(
+                "      <pubDate>{}</pubDate>\n",
+                entry.date.to_rfc2822()
+            ));
+            for tag in terms(entry.page, self.tag_key()) {
+                out.push_str(&format!("      <category>{}</category>\n", xml(tag)));
+            }
+            if let Some(description) = entry.content.as_ref().or(entry.summary.as_ref()) {
+                out.push_str(&format!(
+                    "      <description>{}</description>\n",
+                    xml(description)
+                ));
+            }
+            out.push_str("    </item>\n");
+        }
+        out.push_str("  </channel>\n</rss>\n");
+        out
+    }
+
+    fn json(&self, feed: &Feed, url: &str) -> String {
+        let items: Vec<serde_json::Value> = feed
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut item = serde_json::json!({
+                    "id": entry.url,
+                    "url": entry.url,
+                    "title": entry.title,
+                    // every item has to carry some content even if it's empty
+                    "content_html": entry
+                        .content
+                        .as_ref()
+                        .or(entry.summary.as_ref())
+                        .map(|c| c.as_str())
+                        .unwrap_or_default(),
+                    "tags": terms(entry.page, self.tag_key()).collect::<Vec<_>>(),
+                    "date_published": entry.date.to_string(),
+                });
+                if let (Some(summary), Some(_)) = (&entry.summary, &entry.content) {
+                    item["summary"] = serde_json::Value::String(summary.clone());
+                }
+                item
+            })
+            .collect();
+
+        let mut json = serde_json::json!({
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": feed.title,
+            "home_page_url": feed.home,
+            "feed_url": url,
+            "items": items,
+        });
+        if let Some(author) = &self.options.author {
+            json["authors"] = serde_json::json!([{ "name": author }]);
+        }
+        serde_json::to_string_pretty(&json).unwrap_or_default()
+    }
+}
+
+impl<'a> site::Processor for Feeds<'a> {
+    fn site_rendering<'site>(
+        &self,
+        corpus: &'site content::Corpus,
+        site: &mut site::RenderingSite<'_, 'site, '_>,
+    ) -> crate::Result<()> {
+        let mut articles: Vec<&content::Page> = corpus
+            .pages()
+            .filter(|p| p.meta.kind == PageKind::Article)
+            .collect();
+        articles.sort_by(|a, b| {
+            b.meta
+                .when
+                .cmp(&a.meta.when)
+                .then_with(|| a.meta.title.cmp(&b.meta.title))
+        });
+
+        // readers order by date, an article without one has nowhere to go
+        let entries: Vec<FeedEntry> = articles
+            .into_iter()
+            .filter_map(|page| {
+                let rendered = site.get_by_origin(&page.id)?;
+                Some(FeedEntry {
+                    page,
+                    title: rendered.title.to_string(),
+                    url: site::absolute(self.base, &rendered.url.to_string()),
+                    date: page.meta.when?,
+                    summary: rendered.summary.clone(),
+                    content: match self.options.content {
+                        FeedContent::Summary => None,
+                        FeedContent::Full => Some(render::render_page(
+                            &page.content,
+                            self.highlighter.as_ref(),
+                            false,
+                        )),
+                    },
+                })
+            })
+            .collect();
+
+        let mut feeds = vec![Feed {
+            title: self.title().to_owned(),
+            home: self.base.to_string(),
+            dir: String::new(),
+            entries: entries.iter().take(self.options.limit).collect(),
+        }];
+        if self.options.terms {
+            for taxonomy in self.taxonomies.iter() {
+                // slug -> the term as first written and the entries listed under it
+                let mut by_term: BTreeMap<String, (&str, Vec<&FeedEntry>)> = BTreeMap::new();
+                // a page listing two terms that slugify the same is only listed once
+                let mut listed = HashSet::new();
+                for entry in entries.iter() {
+                    for term in terms(entry.page, &taxonomy.key) {
+                        let slug = slug::slugify(term);
+                        if slug.is_empty() || !listed.insert((slug.clone(), entry.page.id.clone()))
+                        {
+                            continue;
+                        }
+                        by_term
+                            .entry(slug)
+                            .or_insert_with(|| (term, vec![]))
+                            .1
+                            .push(entry);
+                    }
+                }
+                // the term's own page when it was rendered, otherwise the taxonomy's index
+                let index = unsafe { files::FilePath::new(&taxonomy.index_url) };
+                let fallback = if site.occupied(&index) {
+                    site::absolute(self.base, &taxonomy.index_url)
+                } else {
+                    self.base.to_string()
+                };
+                for (slug, (term, mut listed)) in by_term {
+                    listed.truncate(self.options.limit);
+                    let dir = taxonomy.pattern.replace("{slug}", &slug);
+                    let dir = dir.trim_matches('/');
+                    let page = format!("{}/index.html", dir);
+                    let home = if site.occupied(&unsafe { files::FilePath::new(&page) }) {
+                        site::absolute(self.base, &page)
+                    } else {
+                        fallback.clone()
+                    };
+                    feeds.push(Feed {
+                        title: format!("{} - {}", self.title(), term),
+                        home,
+                        dir: dir.to_owned(),
+                        entries: listed,
+                    });
+                }
+            }
+        }
+
+        for feed in feeds.iter() {
+            for format in self.formats.iter() {
+                let path = if feed.dir.is_empty() {
+                    format.file().to_owned()
+                } else {
+                    format!("{}/{}", feed.dir, format.file())
+                };
+                let written = self.write(feed, *format, &site::absolute(self.base, &path));
+                site.add_file(unsafe { files::FilePath::new(path) }, written.into_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// escaped for text and attributes, and without the control characters xml can't hold at all
+pub(super) fn xml<S: AsRef<str>>(s: S) -> String {
+    render::escape(
+        s.as_ref()
+            .chars()
+            .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+            .collect::<String>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{xml, Feed, FeedEntry, Feeds};
+    use crate::{config, content, files, ids, render};
+    use url::Url;
+
+    fn page(title: &str, tags: &[&str]) -> content::Page {
+        let mut ids = ids::IdPool::new(0);
+        let mut builder =
+            content::PageBuilder::new(ids.next(), unsafe { files::FilePath::new("post.md") });
+        builder.with_title(title);
+        builder.meta.insert(
+            "tags".to_owned(),
+            content::Metadata::List(
+                tags.iter()
+                    .map(|t| content::Metadata::Str(t.to_string()))
+                    .collect(),
+            ),
+        );
+        builder.build().unwrap()
+    }
+
+    fn feeds(base: &Url) -> Feeds<'_> {
+        let options = config::Feeds {
+            author: Some("Tom & \"Jerry\"".to_owned()),
+            ..Default::default()
+        };
+        Feeds::new(options, base, vec![], &[], Box::new(render::NullHighligher))
+    }
+
+    #[test]
+    fn xml_escapes_markup_and_drops_control_characters() {
+        assert_eq!(
+            xml("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(xml("bell\u{7}\u{1b}[0m\ttab\nline"), "bell[0m\ttab\nline");
+    }
+
+    #[test]
+    fn escapes_everything_in_xml_feeds() {
+        let base = Url::parse("https://example.com/?a=1&b=2").unwrap();
+        let page = page("Fish & <Chips>\u{0}", &["C & C++", "\"quoted\""]);
+        let entry = FeedEntry {
+            page: &page,
+            title: page.meta.title.clone(),
+            url: "https://example.com/fish?x=1&y=2".to_owned(),
+            date: content::Date::parse("2024-01-02", chrono_tz::UTC).unwrap(),
+            summary: Some("<p>a & b</p>".to_owned()),
+            content: Some("<p>full</p>".to_owned()),
+        };
+        let feed = Feed {
+            title: "Tom's <blog>".to_owned(),
+            home: "https://example.com/?home&page".to_owned(),
+            dir: String::new(),
+            entries: vec![&entry],
+        };
+        let feeds = feeds(&base);
+
+        let atom = feeds.atom(&feed, "https://example.com/atom.xml?v=1&w=2");
+        for expected in [
+            "xml:base=\"https://example.com/?a=1&amp;b=2\"",
+            "<title>Tom&#39;s &lt;blog&gt;</title>",
+            "href=\"https://example.com/atom.xml?v=1&amp;w=2\"",
+            "href=\"https://example.com/?home&amp;page\"",
+            "<name>Tom &amp; &quot;Jerry&quot;</name>",
+            "<title>Fish &amp; &lt;Chips&gt;</title>",
+            "<id>https://example.com/fish?x=1&amp;y=2</id>",
+            "<category term=\"C &amp; C++\" />",
+            "<category term=\"&quot;quoted&quot;\" />",
+            "<summary type=\"html\">&lt;p&gt;a &amp; b&lt;/p&gt;</summary>",
+            "<published>2024-01-02T00:00:00+00:00</published>",
+        ] {
+            assert!(atom.contains(expected), "{} not in\n{}", expected, atom);
+        }
+        assert!(!atom.contains('\u{0}'));
+
+        let rss = feeds.rss(&feed, "https://example.com/rss.xml");
+        for expected in [
+            "<link>https://example.com/?home&amp;page</link>",
+            "<category>C &amp; C++</category>",
+            "<description>&lt;p&gt;full&lt;/p&gt;</description>",
+            "<pubDate>Tue, 2 Jan 2024 00:00:00 +0000</pubDate>",
+        ] {
+            assert!(rss.contains(expected), "{} not in\n{}", expected, rss);
+        }
+
+        // json needs no escaping of its own beyond what serde does, the values come back intact
+        let json: serde_json::Value =
+            serde_json::from_str(&feeds.json(&feed, "https://example.com/feed.json")).unwrap();
+        assert_eq!(json["title"], "Tom's <blog>");
+        assert_eq!(json["authors"][0]["name"], "Tom & \"Jerry\"");
+        assert_eq!(json["items"][0]["title"], "Fish & <Chips>\u{0}");
+        assert_eq!(
+            json["items"][0]["tags"],
+            serde_json::json!(["C & C++", "\"quoted\""])
+        );
+        assert_eq!(json["items"][0]["content_html"], "<p>full</p>");
+        assert_eq!(json["items"][0]["summary"], "<p>a & b</p>");
+        assert_eq!(
+            json["items"][0]["date_published"],
+            "2024-01-02T00:00:00+00:00"
+        );
+    }
+}
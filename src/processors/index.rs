@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+
+use crate::{
+    content::{self, PageKind},
+    site::{self, PageTemplate, RenderedPageMetadata},
+};
+
+use super::{archive::ArchiveEntry, paginate::Pagination};
+
+// the site's home page, every article newest first spread over as many pages as it takes
+pub struct Index<'a> {
+    template: PageTemplate<'a>,
+    pagination: Pagination,
+    tag_key: &'a str,
+}
+
+impl<'a> Index<'a> {
+    pub fn new(template: PageTemplate<'a>, per_page: usize, tag_key: &'a str) -> Self {
+        Self {
+            template,
+            pagination: Pagination::new(per_page),
+            tag_key,
+        }
+    }
+}
+
+impl<'a> site::Processor for Index<'a> {
+    fn site_rendering<'site>(
+        &self,
+        corpus: &'site content::Corpus,
+        site: &mut site::RenderingSite<'_, 'site, '_>,
+    ) -> crate::Result<()> {
+        // a root `_index` page already is the home page
+        if site.occupied(&self.template.url) {
+            eprintln!(
+                "not writing the home page, {} is already taken, turn off the index transformer \
+                 to silence this",
+                self.template.url
+            );
+            return Ok(());
+        }
+        if !site.renderer().has_template(self.template.template) {
+            return Err(Box::new(site::SiteError::MissingTemplate(
+                self.template.template.to_owned(),
+            )));
+        }
+
+        let mut articles: Vec<&content::Page> = corpus
+            .pages()
+            .filter(|p| p.meta.kind == PageKind::Article)
+            .collect();
+        articles.sort_by(|a, b| {
+            b.meta
+                .when
+                .cmp(&a.meta.when)
+                .then_with(|| a.meta.title.cmp(&b.meta.title))
+        });
+
+        let entries: Vec<minijinja::Value> = articles
+            .iter()
+            .filter_map(|p| {
+                let rendered = site.get_by_origin(&p.id)?;
+                Some(minijinja::Value::from_serialize(ArchiveEntry::new(
+                    site.base_url(),
+                    rendered,
+                    p,
+                    self.tag_key,
+                )))
+            })
+            .collect();
+
+        for (url, paginator, pages) in
+            self.pagination
+                .pages(site.base_url(), &self.template.url, &entries)
+        {
+            let mut page = site.page(self.template.template);
+            page.values().merge(minijinja::context! {
+                pages => pages,
+                paginator => minijinja::Value::from_serialize(&paginator),
+            });
+            let meta = RenderedPageMetadata {
+                url: Cow::Owned(url),
+                ..self.template.stamp()
+            };
+            site.render_page(meta, page)?;
+        }
+
+        Ok(())
+    }
+}
@@ -2,12 +2,22 @@
 
 mod archive;
 mod cleaner;
+mod feed;
+mod index;
+mod paginate;
+mod sitemap;
 mod staticfiles;
-mod tag;
+mod terms;
 mod toc;
 
-pub use archive::{Archive, Archivist, DateArchivist, TagArchivist, TagSorting};
+pub use archive::{
+    Archive, Archivist, DateArchivist, DateGrouping, Sorting, TermArchivist, TermOrdering,
+};
 pub use cleaner::Cleaner;
+pub use feed::{FeedContent, FeedFormat, Feeds};
+pub use index::Index;
+pub use paginate::{Pagination, Paginator};
+pub use sitemap::Sitemap;
 pub use staticfiles::StaticFiles;
-pub use tag::Tags;
+pub use terms::Terms;
 pub use toc::Toc;
@@ -0,0 +1,153 @@
+use url::Url;
+
+use crate::{files, site};
+
+// splits a listing across pages, the first keeps the listing's own url and the rest
+// go under it: index.html -> page/2/index.html, tags/rust/index.html ->
+// tags/rust/page/2/index.html, archive.html -> archive/page/2/index.html
+#[derive(Debug, Clone, Copy)]
+pub struct Pagination {
+    per_page: usize,
+}
+
+// what templates see about where a page sits in its listing
+#[derive(Debug, serde::Serialize)]
+pub struct Paginator {
+    current: usize,
+    total: usize,
+    prev: Option<String>,
+    next: Option<String>,
+}
+
+impl Pagination {
+    pub fn new(per_page: usize) -> Self {
+        Self {
+            per_page: per_page.max(1),
+        }
+    }
+
+    pub fn pages<'t, T>(
+        &self,
+        base: &Url,
+        first: &files::FilePath,
+        items: &'t [T],
+    ) -> Vec<(files::FilePath, Paginator, &'t [T])> {
+        // an empty listing still gets its first page
+        let chunks: Vec<&[T]> = if items.is_empty() {
+            vec![items]
+        } else {
+            items.chunks(self.per_page).collect()
+        };
+        let total = chunks.len();
+        let urls: Vec<files::FilePath> = (1..=total).map(|n| page_url(first, n)).collect();
+        let href = |n: usize| urls.get(n - 1).map(|u| site::href(base, &u.to_string()));
+
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let current = i + 1;
+                let paginator = Paginator {
+                    current,
+                    total,
+                    prev: if current > 1 { href(current - 1) } else { None },
+                    next: href(current + 1),
+                };
+                (urls[i].clone(), paginator, chunk)
+            })
+            .collect()
+    }
+}
+
+fn page_url(first: &files::FilePath, n: usize) -> files::FilePath {
+    if n == 1 {
+        return first.clone();
+    }
+    let first = first.to_string();
+    let base = match first.strip_suffix("index.html") {
+        Some(dir) => dir.trim_end_matches('/'),
+        None => first.strip_suffix(".html").unwrap_or(&first),
+    };
+    let url = if base.is_empty() {
+        format!("page/{}/index.html", n)
+    } else {
+        format!("{}/page/{}/index.html", base, n)
+    };
+    unsafe { files::FilePath::new(url) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{page_url, Pagination};
+    use crate::files;
+    use url::Url;
+
+    fn url(s: &str) -> files::FilePath {
+        unsafe { files::FilePath::new(s) }
+    }
+
+    #[test]
+    fn later_pages_go_under_the_listing() {
+        for (first, second) in [
+            ("index.html", "page/2/index.html"),
+            ("tags/rust/index.html", "tags/rust/page/2/index.html"),
+            ("archive.html", "archive/page/2/index.html"),
+            ("tags/rust.html", "tags/rust/page/2/index.html"),
+        ] {
+            assert_eq!(page_url(&url(first), 1).to_string(), first);
+            assert_eq!(page_url(&url(first), 2).to_string(), second);
+        }
+    }
+
+    #[test]
+    fn links_pages_under_the_base_path() {
+        let base = Url::parse("https://example.com/blog/").unwrap();
+        let items = [1, 2, 3, 4, 5];
+        let pages = Pagination::new(2).pages(&base, &url("archive.html"), &items);
+        assert_eq!(pages.len(), 3);
+
+        let (urls, chunks): (Vec<_>, Vec<_>) = pages
+            .iter()
+            .map(|(url, _, chunk)| (url.to_string(), *chunk))
+            .unzip();
+        assert_eq!(
+            urls,
+            [
+                "archive.html",
+                "archive/page/2/index.html",
+                "archive/page/3/index.html"
+            ]
+        );
+        assert_eq!(chunks, [&[1, 2][..], &[3, 4], &[5]]);
+
+        let links: Vec<_> = pages
+            .iter()
+            .map(|(_, p, _)| (p.current, p.total, p.prev.as_deref(), p.next.as_deref()))
+            .collect();
+        assert_eq!(
+            links,
+            [
+                (1, 3, None, Some("/blog/archive/page/2/index.html")),
+                (
+                    2,
+                    3,
+                    Some("/blog/archive.html"),
+                    Some("/blog/archive/page/3/index.html")
+                ),
+                (3, 3, Some("/blog/archive/page/2/index.html"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn an_empty_listing_still_gets_a_page() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let pages = Pagination::new(0).pages::<u8>(&base, &url("index.html"), &[]);
+        assert_eq!(pages.len(), 1);
+        let (url, paginator, items) = &pages[0];
+        assert_eq!(url.to_string(), "index.html");
+        assert_eq!((paginator.current, paginator.total), (1, 1));
+        assert!(paginator.prev.is_none() && paginator.next.is_none());
+        assert!(items.is_empty());
+    }
+}
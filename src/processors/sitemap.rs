@@ -0,0 +1,209 @@
+use url::Url;
+
+use crate::{config, content, files, site};
+
+use super::feed::xml;
+
+// https://www.sitemaps.org/protocol.html caps a single sitemap at this many urls
+const MAX_URLS: usize = 50_000;
+
+// every rendered page, less the ones whose frontmatter opts out, as sitemap.xml plus a
+// robots.txt pointing at it
+pub struct Sitemap<'a> {
+    base: &'a Url,
+    options: config::Sitemap,
+}
+
+struct Location {
+    url: String,
+    lastmod: Option<content::Date>,
+}
+
+impl<'a> Sitemap<'a> {
+    pub fn new(base: &'a Url, options: config::Sitemap) -> Self {
+        Self { base, options }
+    }
+
+    fn robots(&self, sitemap: &str) -> Option<String> {
+        let mut robots = match &self.options.robots {
+            config::Robots::Off => return None,
+            config::Robots::Custom(text) => text.clone(),
+            config::Robots::Default => {
+                let mut robots = "User-agent: *\n".to_owned();
+                for path in self.options.disallow.iter() {
+                    robots.push_str(&format!("Disallow: {}\n", path));
+                }
+                // an empty disallow lets crawlers everywhere
+                if self.options.disallow.is_empty() {
+                    robots.push_str("Disallow:\n");
+                }
+                robots
+            }
+        };
+        if !robots.ends_with('\n') {
+            robots.push('\n');
+        }
+        robots.push_str(&format!("\nSitemap: {}\n", sitemap));
+        Some(robots)
+    }
+}
+
+// frontmatter can keep a page out with `sitemap: false` or `noindex: true`
+fn excluded(page: &content::Page) -> bool {
+    matches!(
+        page.meta.meta.get("sitemap"),
+        Some(content::Metadata::Bool(false))
+    ) || matches!(
+        page.meta.meta.get("noindex"),
+        Some(content::Metadata::Bool(true))
+    )
+}
+
+fn urlset(locations: &[Location]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for location in locations {
+        out.push_str("  <url>\n");
+        out.push_str(&format!("    <loc>{}</loc>\n", xml(&location.url)));
+        if let Some(lastmod) = location.lastmod {
+            out.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod));
+        }
+        out.push_str("  </url>\n");
+    }
+    out.push_str("</urlset>\n");
+    out
+}
+
+fn index(sitemaps: &[String]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for sitemap in sitemaps {
+        out.push_str(&format!(
+            "  <sitemap>\n    <loc>{}</loc>\n  </sitemap>\n",
+            xml(sitemap)
+        ));
+    }
+    out.push_str("</sitemapindex>\n");
+    out
+}
+
+impl<'a> site::Processor for Sitemap<'a> {
+    fn site_rendering<'site>(
+        &self,
+        corpus: &'site content::Corpus,
+        site: &mut site::RenderingSite<'_, 'site, '_>,
+    ) -> crate::Result<()> {
+        let mut locations: Vec<Location> = vec![];
+        for rendered in site.pages() {
+            let page = rendered.origin.as_ref().and_then(|id| corpus.page(id));
+            if page.is_some_and(excluded) {
+                continue;
+            }
+            locations.push(Location {
+                url: site::absolute(self.base, &rendered.url.to_string()),
+                lastmod: page.and_then(|p| p.meta.when),
+            });
+        }
+        // the same site always produces the same sitemap
+        locations.sort_by(|a, b| a.url.cmp(&b.url));
+        locations.dedup_by(|a, b| a.url == b.url);
+
+        let sitemap = site::absolute(self.base, "sitemap.xml");
+        if locations.len() <= MAX_URLS {
+            site.add_file(
+                unsafe { files::FilePath::new("sitemap.xml") },
+                urlset(&locations).into_bytes(),
+            )?;
+        } else {
+            let mut sitemaps = vec![];
+            for (i, chunk) in locations.chunks(MAX_URLS).enumerate() {
+                let path = format!("sitemap-{}.xml", i + 1);
+                sitemaps.push(site::absolute(self.base, &path));
+                site.add_file(
+                    unsafe { files::FilePath::new(path) },
+                    urlset(chunk).into_bytes(),
+                )?;
+            }
+            site.add_file(
+                unsafe { files::FilePath::new("sitemap.xml") },
+                index(&sitemaps).into_bytes(),
+            )?;
+        }
+
+        if let Some(robots) = self.robots(&sitemap) {
+            site.add_file(
+                unsafe { files::FilePath::new("robots.txt") },
+                robots.into_bytes(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{index, urlset, Location, Sitemap};
+    use crate::{config, content};
+    use url::Url;
+
+    #[test]
+    fn escapes_locations() {
+        let locations = [
+            Location {
+                url: "https://example.com/search?q=a&b=<c>".to_owned(),
+                lastmod: None,
+            },
+            Location {
+                url: "https://example.com/post.html".to_owned(),
+                lastmod: Some(content::Date::parse("2024-01-02", chrono_tz::UTC).unwrap()),
+            },
+        ];
+        assert_eq!(
+            urlset(&locations),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n  \
+             <url>\n    <loc>https://example.com/search?q=a&amp;b=&lt;c&gt;</loc>\n  </url>\n  \
+             <url>\n    <loc>https://example.com/post.html</loc>\n    \
+             <lastmod>2024-01-02T00:00:00+00:00</lastmod>\n  </url>\n\
+             </urlset>\n"
+        );
+        assert!(index(&["https://example.com/sitemap-1.xml?a&b".to_owned()])
+            .contains("<loc>https://example.com/sitemap-1.xml?a&amp;b</loc>"));
+    }
+
+    #[test]
+    fn robots_point_at_the_sitemap() {
+        let base = Url::parse("https://example.com/").unwrap();
+        let robots = |robots, disallow: &[&str]| {
+            Sitemap::new(
+                &base,
+                config::Sitemap {
+                    robots,
+                    disallow: disallow.iter().map(|d| d.to_string()).collect(),
+                },
+            )
+            .robots("https://example.com/sitemap.xml")
+        };
+        assert_eq!(
+            robots(config::Robots::Default, &[]).unwrap(),
+            "User-agent: *\nDisallow:\n\nSitemap: https://example.com/sitemap.xml\n"
+        );
+        assert_eq!(
+            robots(config::Robots::Default, &["/drafts/", "/tmp/"]).unwrap(),
+            "User-agent: *\nDisallow: /drafts/\nDisallow: /tmp/\n\n\
+             Sitemap: https://example.com/sitemap.xml\n"
+        );
+        assert_eq!(
+            robots(
+                config::Robots::Custom("User-agent: BadBot\nDisallow: /".to_owned()),
+                &[]
+            )
+            .unwrap(),
+            "User-agent: BadBot\nDisallow: /\n\nSitemap: https://example.com/sitemap.xml\n"
+        );
+        assert!(robots(config::Robots::Off, &[]).is_none());
+    }
+}
@@ -1,29 +1,45 @@
-use std::borrow::Cow;
+use std::path::PathBuf;
 
-use crate::{
-    content::{self, IncludedPath},
-    jinja, site,
-};
+use crate::{content, files, site};
 
-pub struct StaticFiles(pub(crate) IncludedPath);
+// copies a file or everything in a directory into the output under `dest`
+pub struct StaticFiles {
+    src: files::Path,
+    dest: PathBuf,
+    filter: files::Filter,
+}
 
-impl site::Processor for StaticFiles {
-    fn initialize<'call, 'init>(
-        &'call mut self,
-        _: &'call mut site::initializer::Initializer<'init, '_>,
-    ) -> crate::Result<()>
-    where
-        'init: 'call,
-    {
-        println!("let's pretend I'm loading assets from {:?}", self.0);
-        Ok(())
+impl StaticFiles {
+    // without a destination things are placed under their own name at the root of the output
+    pub fn new(src: files::Path, dest: Option<&str>, filter: files::Filter) -> StaticFiles {
+        let dest = match dest {
+            Some(dest) => PathBuf::from(dest.trim_start_matches('/')),
+            None => AsRef::<std::path::Path>::as_ref(&src)
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_default(),
+        };
+        Self { src, dest, filter }
     }
+}
 
-    fn site_rendering<'site>(
-        &self,
-        _: &'site content::Corpus,
-        _: &mut site::rendered::RenderingSite<'_, 'site, '_>,
-    ) -> crate::Result<()> {
-        Ok(())
+impl site::Processor for StaticFiles {
+    fn site_loaded(&mut self, corpus: &mut content::Corpus) -> crate::Result<()> {
+        match &self.src {
+            files::Path::File(f) => corpus.include_asset(f.clone(), self.dest.clone()),
+            files::Path::Dir(d) => {
+                let walk = files::Walker::filtered(
+                    d,
+                    files::RecursionBehavior::Recurse,
+                    self.filter.clone(),
+                );
+                for file in walk {
+                    let relative = file.strip_prefix(d).unwrap_or(file.as_path());
+                    let dest = self.dest.join(relative);
+                    corpus.include_asset(file, dest)?;
+                }
+                Ok(())
+            }
+        }
     }
 }
@@ -0,0 +1,33 @@
+use crate::{
+    config, content,
+    site::{self, RenderingPage},
+};
+
+use super::archive::terms;
+
+// puts each taxonomy's terms on the pages that list them, under the taxonomy's name
+pub struct Terms<'a>(pub &'a [config::Taxonomy]);
+
+impl<'a> site::Processor for Terms<'a> {
+    fn page_rendering<'render, 'site>(
+        &self,
+        page: &content::Page,
+        rendering: &mut RenderingPage<'render, 'site>,
+    ) -> crate::Result<()>
+    where
+        'site: 'render,
+    {
+        for taxonomy in self.0.iter() {
+            if !page.meta.meta.contains_key(&taxonomy.key) {
+                continue;
+            }
+            let listed: Vec<&str> = terms(page, &taxonomy.key).collect();
+            rendering.values().merge(minijinja::Value::from_iter([(
+                taxonomy.name.as_str(),
+                minijinja::Value::from_serialize(&listed),
+            )]));
+        }
+
+        Ok(())
+    }
+}
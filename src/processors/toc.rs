@@ -1,10 +1,11 @@
-use std::marker::PhantomData;
-
 use crate::{
-    content::doctree::Element,
-    site::{self, PageTemplate, RenderingPage},
+    content::{self, doctree::Element, Metadata},
+    render::escape,
+    site::{self, RenderingPage},
 };
 
+// gives templates the page's headings down to `depth` as `toc`, nested under the heading they
+// belong to, and as a ready made list in `toc_html`. pages opt out with `toc: false`
 pub struct Toc {
     pub(crate) depth: u8,
 }
@@ -12,20 +13,152 @@ pub struct Toc {
 impl site::Processor for Toc {
     fn page_rendering<'render, 'site>(
         &self,
-        _: &crate::content::Page,
-        _: &mut RenderingPage<'render, 'site>,
+        page: &content::Page,
+        rendering: &mut RenderingPage<'render, 'site>,
     ) -> crate::Result<()>
     where
         'site: 'render,
     {
+        if let Some(Metadata::Bool(false)) = page.meta.meta.get("toc") {
+            return Ok(());
+        }
+
+        let toc = self.headings(&page.content.content);
+        if toc.is_empty() {
+            return Ok(());
+        }
+
+        let mut html = String::new();
+        Self::render(&toc, &mut html);
+        rendering.values().merge(minijinja::context! {
+            toc => toc,
+            toc_html => minijinja::Value::from_safe_string(html),
+        });
         Ok(())
     }
 }
 
-impl Toc {}
+impl Toc {
+    fn headings<'render>(&self, elms: &'render [Element]) -> Vec<Heading<'render>> {
+        let mut flat = vec![];
+        self.collect(elms, &mut flat);
+
+        // every heading is pushed onto a stack of the headings above it and attached to its
+        // parent once something at the same depth or higher comes along
+        let mut roots = vec![];
+        let mut stack: Vec<Heading> = vec![];
+        for heading in flat {
+            while matches!(stack.last(), Some(top) if top.depth >= heading.depth) {
+                Self::attach(stack.pop().unwrap(), &mut stack, &mut roots);
+            }
+            stack.push(heading);
+        }
+        while let Some(top) = stack.pop() {
+            Self::attach(top, &mut stack, &mut roots);
+        }
+        roots
+    }
+
+    fn collect<'render>(&self, elms: &'render [Element], into: &mut Vec<Heading<'render>>) {
+        for elm in elms {
+            match elm {
+                Element::Group(g) => self.collect(g.children(), into),
+                Element::Heading(h) if h.depth() <= self.depth => into.push(Heading {
+                    depth: h.depth(),
+                    text: h.text(),
+                    id: h.label(),
+                    children: vec![],
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    fn attach<'render>(
+        heading: Heading<'render>,
+        stack: &mut [Heading<'render>],
+        roots: &mut Vec<Heading<'render>>,
+    ) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(heading),
+            None => roots.push(heading),
+        }
+    }
+
+    fn render(headings: &[Heading], html: &mut String) {
+        html.push_str("<ul class=\"toc\">");
+        for heading in headings {
+            html.push_str(&format!(
+                "<li><a href=\"#{}\">{}</a>",
+                escape(heading.id),
+                escape(heading.text)
+            ));
+            if !heading.children.is_empty() {
+                Self::render(&heading.children, html);
+            }
+            html.push_str("</li>");
+        }
+        html.push_str("</ul>");
+    }
+}
 
+#[derive(serde::Serialize)]
 struct Heading<'render> {
     depth: u8,
-    display: &'render str,
+    text: &'render str,
     id: &'render str,
+    children: Vec<Heading<'render>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Toc;
+    use crate::content::doctree::{Element, Group, Header};
+
+    fn heading(depth: u8, text: &str) -> Element {
+        Element::Heading(Header::create(
+            depth,
+            Group::default(),
+            text.to_owned(),
+            text.to_lowercase().replace(' ', "-"),
+        ))
+    }
+
+    fn toc(depth: u8, elms: &[Element]) -> String {
+        let mut html = String::new();
+        let toc = Toc { depth };
+        Toc::render(&toc.headings(elms), &mut html);
+        html
+    }
+
+    #[test]
+    fn nests_headings_under_the_one_above() {
+        let page = [
+            heading(2, "Intro"),
+            heading(3, "Why"),
+            heading(4, "Deep"),
+            heading(3, "How"),
+            heading(2, "End"),
+        ];
+        assert_eq!(
+            toc(6, &page),
+            "<ul class=\"toc\"><li><a href=\"#intro\">Intro</a><ul class=\"toc\">\
+             <li><a href=\"#why\">Why</a><ul class=\"toc\"><li><a href=\"#deep\">Deep</a></li></ul></li>\
+             <li><a href=\"#how\">How</a></li></ul></li><li><a href=\"#end\">End</a></li></ul>"
+        );
+        assert_eq!(
+            toc(2, &page),
+            "<ul class=\"toc\"><li><a href=\"#intro\">Intro</a></li><li><a href=\"#end\">End</a></li></ul>"
+        );
+    }
+
+    #[test]
+    fn headings_deeper_than_the_first_become_roots() {
+        let page = [heading(3, "Sub"), heading(1, "Top"), heading(2, "A & B")];
+        assert_eq!(
+            toc(3, &page),
+            "<ul class=\"toc\"><li><a href=\"#sub\">Sub</a></li><li><a href=\"#top\">Top</a>\
+             <ul class=\"toc\"><li><a href=\"#a-&amp;-b\">A &amp; B</a></li></ul></li></ul>"
+        );
+    }
 }
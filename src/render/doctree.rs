@@ -1,4 +1,4 @@
-use super::{CodeHighlighter, DisplayableOption, NullHighligher};
+use super::{escape, CodeHighlighter, DisplayableOption};
 use crate::{
     content,
     content::doctree::{self, Definition, DefinitionLookup},
@@ -50,10 +50,16 @@ impl PageBuffer {
     }
 }
 
-pub fn render_page(page: &content::PageContents) -> String {
+// anchors puts a link to each heading next to it
+pub fn render_page(
+    page: &content::PageContents,
+    highlighter: &dyn CodeHighlighter,
+    anchors: bool,
+) -> String {
     let mut buffer = PageBuffer::new();
     let helper = DoctreeRenderer {
-        highlighter: Box::new(NullHighligher),
+        highlighter,
+        anchors,
         doctree: &page.content,
         footnotes: &page.footnotes,
         hrefs: &page.hrefs,
@@ -61,7 +67,9 @@ pub fn render_page(page: &content::PageContents) -> String {
     buffer.push_line("<article>");
     buffer.indent();
     helper.render(&mut buffer);
-    helper.include_footnotes(&mut buffer);
+    if page.footnotes.definitions().next().is_some() {
+        helper.include_footnotes(&mut buffer);
+    }
     buffer.push_line("</article>");
     buffer.flush()
 }
@@ -70,11 +78,13 @@ pub fn render_summary(
     summary: &Vec<doctree::Element>,
     footnotes: &content::Definitions<doctree::FootnoteDefinition>,
     hrefs: &content::Definitions<doctree::HrefDefinition>,
+    highlighter: &dyn CodeHighlighter,
 ) -> String {
     let mut buffer = PageBuffer::new();
     let helper = DoctreeRenderer {
         doctree: summary,
-        highlighter: Box::new(NullHighligher),
+        highlighter,
+        anchors: false,
         footnotes,
         hrefs,
     };
@@ -86,7 +96,8 @@ struct DoctreeRenderer<'a> {
     doctree: &'a Vec<doctree::Element>,
     footnotes: &'a content::Definitions<doctree::FootnoteDefinition>,
     hrefs: &'a content::Definitions<doctree::HrefDefinition>,
-    highlighter: Box<dyn CodeHighlighter>,
+    highlighter: &'a dyn CodeHighlighter,
+    anchors: bool,
 }
 
 impl<'a> DoctreeRenderer<'a> {
@@ -101,7 +112,7 @@ impl<'a> DoctreeRenderer<'a> {
 
         for ftn in self.footnotes.definitions() {
             self.wrap_children_block(
-                format!("<li id=\"{}\">", ftn.label()),
+                format!("<li id=\"{}\">", escape(ftn.label())),
                 ftn.children(),
                 "</li>",
                 buffer,
@@ -133,8 +144,11 @@ impl<'a> DoctreeRenderer<'a> {
             HrefReference(h) => self.href_reference(h, buffer),
             ImageReference(i) => self.image_reference(i, buffer),
             InlineCode(i) => self.inline_code(i, buffer),
+            LineBreak => buffer.push_line("<br />"),
             List(l) => self.list(l, buffer),
             Paragraph(p) => self.paragraph(p, buffer),
+            RawHtml(h) => buffer.push(h),
+            Rule => buffer.push_line("<hr />"),
             Strong(s) => self.strong(s, buffer),
             Table(t) => self.table(t, buffer),
             Text(t) => self.text(t, buffer),
@@ -178,14 +192,42 @@ impl<'a> DoctreeRenderer<'a> {
     }
 
     fn codeblock(&self, c: &doctree::Code, buffer: &mut PageBuffer) {
+        let opts = c.options();
+        let title = match &opts.title {
+            None => String::new(),
+            Some(title) => format!("<div class=\"codeblock-title\">{}</div>", escape(title)),
+        };
         buffer.push_line(format!(
-            "<div class=\"codeblock {}\"><pre><code>",
-            DisplayableOption {
-                value: c.lang(),
-                or: ""
-            }
+            "<div class=\"codeblock {}\">{}<pre><code>",
+            escape(
+                DisplayableOption {
+                    value: c.lang(),
+                    or: ""
+                }
+                .to_string()
+            ),
+            title
         ));
-        buffer.push_line(self.highlighter.highlight(c).as_str());
+        let lines: String = self
+            .highlighter
+            .highlight(c)
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                let open = if opts.highlighted(idx + 1) {
+                    "<span class=\"hl\">"
+                } else {
+                    "<span>"
+                };
+                let lineno = if opts.linenos {
+                    format!("<span class=\"lineno\">{}</span>", opts.start.max(1) + idx)
+                } else {
+                    String::new()
+                };
+                format!("{}{}{}</span>\n", open, lineno, line)
+            })
+            .collect();
+        buffer.push_line(lines);
         buffer.push_line("</code></pre></div>");
     }
 
@@ -200,23 +242,28 @@ impl<'a> DoctreeRenderer<'a> {
     fn footnote_reference(&self, f: &doctree::FootnoteReference, buffer: &mut PageBuffer) {
         buffer.push(format!(
             "<span class=\"footnote reference\"><a href=\"#{0}\">{0}</a></span>",
-            f
+            escape(f.to_string())
         ));
     }
 
     fn heading(&self, d: &doctree::Header, buffer: &mut PageBuffer) {
-        buffer.push_line(format!(
-            "<h{0} id=\"{1}\"><span>{2}</span></h{0}>",
-            d.depth(),
-            d.label(),
-            d.text()
-        ));
+        let id = escape(d.label());
+        buffer.push(format!("<h{} id=\"{}\"><span>", d.depth(), id));
+        self.render_elms(d.children().children(), buffer);
+        buffer.push("</span>");
+        if self.anchors {
+            buffer.push(format!(
+                "<a class=\"anchor\" href=\"#{}\" aria-hidden=\"true\">#</a>",
+                id
+            ));
+        }
+        buffer.push(format!("</h{}>\n", d.depth()));
     }
 
     fn href_reference(&self, d: &doctree::HrefReference, buffer: &mut PageBuffer) {
         let def = self.hrefs.lookup(d).unwrap();
         self.wrap_children_inline(
-            format!("<a href=\"{}\">", def.href()),
+            format!("<a href=\"{}\">", escape(def.href().to_string())),
             d.children(),
             "</a>",
             buffer,
@@ -224,21 +271,57 @@ impl<'a> DoctreeRenderer<'a> {
     }
     fn image_reference(&self, d: &doctree::ImageReference, buffer: &mut PageBuffer) {
         let def = self.hrefs.lookup(d).unwrap();
-        buffer.push(format!("<img href=\"{}\" />", def.href()));
+        buffer.push(format!(
+            "<img src=\"{}\" alt=\"{}\" />",
+            escape(def.href().to_string()),
+            escape(d.alt())
+        ));
     }
 
     fn inline_code(&self, d: &doctree::Code, buffer: &mut PageBuffer) {
         buffer.push(format!("<span class=\"code inline\"><code>"));
-        buffer.push(d.content());
+        buffer.push(escape(d.content()));
         buffer.push("</code></span>");
     }
 
     fn list(&self, d: &doctree::List, buffer: &mut PageBuffer) {
-        buffer.push_line("<ul>");
+        let close = match d.start() {
+            None => {
+                buffer.push_line("<ul>");
+                "</ul>"
+            }
+            Some(1) => {
+                buffer.push_line("<ol>");
+                "</ol>"
+            }
+            Some(start) => {
+                buffer.push_line(format!("<ol start=\"{}\">", start));
+                "</ol>"
+            }
+        };
         for item in d.items() {
-            self.wrap_children_block("<li>", item.children(), "</li>", buffer);
+            self.list_item(item, d.is_tight(), buffer);
+        }
+        buffer.push_line(close);
+    }
+
+    fn list_item(&self, d: &doctree::ListItem, tight: bool, buffer: &mut PageBuffer) {
+        buffer.push_line("<li>");
+        buffer.indent();
+        match d.checked() {
+            None => {}
+            Some(true) => buffer.push("<input type=\"checkbox\" disabled=\"\" checked=\"\" /> "),
+            Some(false) => buffer.push("<input type=\"checkbox\" disabled=\"\" /> "),
         }
-        buffer.push_line("</ul>");
+        for elm in d.children().children() {
+            match elm {
+                // paragraphs of a tight list aren't wrapped
+                doctree::Element::Paragraph(p) if tight => self.render_elms(p.children(), buffer),
+                elm => self.render_elm(elm, buffer),
+            }
+        }
+        buffer.dedent();
+        buffer.push_line("</li>");
     }
 
     fn paragraph(&self, d: &doctree::Group, buffer: &mut PageBuffer) {
@@ -252,20 +335,45 @@ impl<'a> DoctreeRenderer<'a> {
     fn table(&self, d: &doctree::Table, buffer: &mut PageBuffer) {
         buffer.push_line("<table>");
 
-        for row in d.rows() {
-            buffer.push_line("<tr>");
+        if let Some(head) = d.head() {
+            buffer.push_line("<thead>");
+            self.table_row(d, head, "th", buffer);
+            buffer.push_line("</thead>");
+        }
 
-            for cell in row.cells() {
-                self.wrap_children_block("<td>", cell.children(), "</td>", buffer);
+        let mut body = d.body().peekable();
+        if body.peek().is_some() {
+            buffer.push_line("<tbody>");
+            for row in body {
+                self.table_row(d, row, "td", buffer);
             }
-
-            buffer.push_line("</tr>");
+            buffer.push_line("</tbody>");
         }
 
         buffer.push_line("</table>");
     }
 
+    fn table_row(
+        &self,
+        table: &doctree::Table,
+        row: &doctree::TableRow,
+        tag: &str,
+        buffer: &mut PageBuffer,
+    ) {
+        buffer.push_line("<tr>");
+        for (column, cell) in row.cells().enumerate() {
+            let open = match table.alignment(column) {
+                doctree::Alignment::None => format!("<{}>", tag),
+                doctree::Alignment::Left => format!("<{} align=\"left\">", tag),
+                doctree::Alignment::Right => format!("<{} align=\"right\">", tag),
+                doctree::Alignment::Center => format!("<{} align=\"center\">", tag),
+            };
+            self.wrap_children_block(open, cell.children(), format!("</{}>", tag), buffer);
+        }
+        buffer.push_line("</tr>");
+    }
+
     fn text(&self, d: &doctree::Text, buffer: &mut PageBuffer) {
-        buffer.push(d);
+        buffer.push(escape(d));
     }
 }
@@ -1,17 +1,166 @@
-use crate::content::doctree;
+use std::fmt::Write;
 
-pub trait CodeHighlighter {
-    fn highlight(&self, code: &doctree::Code) -> String;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    html::{self, ClassStyle, IncludeBackground},
+    parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+use super::escape;
+use crate::{content::doctree, files};
+
+const CLASS_PREFIX: &str = "hl-";
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: CLASS_PREFIX,
+};
+
+// highlighting happens while pages are rendered across threads
+pub trait CodeHighlighter: Send + Sync {
+    // one entry per line of code, every entry is valid html on its own
+    fn highlight(&self, code: &doctree::Code) -> Vec<String>;
+
+    // anything the highlighted output needs to look right, written next to the pages
+    fn stylesheet(&self) -> Option<(files::FilePath, String)> {
+        None
+    }
 }
 
 pub struct NullHighligher;
 
 impl CodeHighlighter for NullHighligher {
-    fn highlight(&self, code: &doctree::Code) -> String {
-        let mut buffer = String::new();
-        for line in code.content().lines() {
-            buffer.push_str(format!("<span>{}</span>\n", line).as_str());
+    fn highlight(&self, code: &doctree::Code) -> Vec<String> {
+        code.content().lines().map(escape).collect()
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub enum HighlightStyle {
+    // spans carry classes, the theme is turned into a stylesheet written to `stylesheet`
+    Classes { stylesheet: files::FilePath },
+    // spans carry the theme's colors directly
+    Inline,
+}
+
+#[derive(Debug)]
+pub enum HighlightError {
+    UnknownTheme(String),
+}
+
+impl std::fmt::Display for HighlightError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use HighlightError::*;
+        write!(f, "HighlightError::")?;
+        match self {
+            UnknownTheme(theme) => write!(f, "UnknownTheme({})", theme),
+        }
+    }
+}
+
+impl std::error::Error for HighlightError {}
+
+// grammars and themes are the ones bundled with syntect, languages it doesn't know are passed
+// through the same way the null highlighter does it
+pub struct SyntectHighlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+    style: HighlightStyle,
+}
+
+impl SyntectHighlighter {
+    pub fn new(theme: &str, style: HighlightStyle) -> crate::Result<SyntectHighlighter> {
+        let mut themes = ThemeSet::load_defaults();
+        let theme = themes
+            .themes
+            .remove(theme)
+            .ok_or_else(|| HighlightError::UnknownTheme(theme.to_owned()))?;
+
+        Ok(Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme,
+            style,
+        })
+    }
+
+    fn syntax(&self, code: &doctree::Code) -> Option<&SyntaxReference> {
+        code.lang()
+            .as_ref()
+            .and_then(|lang| self.syntaxes.find_syntax_by_token(lang.as_ref()))
+    }
+
+    // the class generator lets spans run across lines, so every line closes whatever is still
+    // open at its end and the next line opens it again
+    fn classed(&self, syntax: &SyntaxReference, code: &str) -> Result<Vec<String>, syntect::Error> {
+        let mut state = ParseState::new(syntax);
+        let mut stack = ScopeStack::new();
+        let mut lines = Vec::new();
+
+        for line in LinesWithEndings::from(code) {
+            let mut html = String::new();
+            for scope in stack.as_slice() {
+                let classes: Vec<_> = scope
+                    .build_string()
+                    .split('.')
+                    .map(|atom| format!("{CLASS_PREFIX}{atom}"))
+                    .collect();
+                write!(html, "<span class=\"{}\">", classes.join(" "))?;
+            }
+
+            let ops = state.parse_line(line, &self.syntaxes)?;
+            let (spans, _) =
+                html::line_tokens_to_classed_spans(line, &ops, CLASS_STYLE, &mut stack)?;
+            html.push_str(&spans);
+            html.push_str(&"</span>".repeat(stack.len()));
+            lines.push(without_newline(html));
+        }
+
+        Ok(lines)
+    }
+
+    fn inline(&self, syntax: &SyntaxReference, code: &str) -> Result<Vec<String>, syntect::Error> {
+        let mut highlighter = HighlightLines::new(syntax, &self.theme);
+        LinesWithEndings::from(code)
+            .map(|line| {
+                let regions = highlighter.highlight_line(line, &self.syntaxes)?;
+                html::styled_line_to_highlighted_html(&regions, IncludeBackground::No)
+                    .map(without_newline)
+            })
+            .collect()
+    }
+}
+
+impl CodeHighlighter for SyntectHighlighter {
+    fn highlight(&self, code: &doctree::Code) -> Vec<String> {
+        let syntax = match self.syntax(code) {
+            None => return NullHighligher.highlight(code),
+            Some(s) => s,
+        };
+
+        let highlighted = match self.style {
+            HighlightStyle::Classes { .. } => self.classed(syntax, code.content().as_ref()),
+            HighlightStyle::Inline => self.inline(syntax, code.content().as_ref()),
+        };
+
+        highlighted.unwrap_or_else(|_| NullHighligher.highlight(code))
+    }
+
+    fn stylesheet(&self) -> Option<(files::FilePath, String)> {
+        match &self.style {
+            HighlightStyle::Inline => None,
+            HighlightStyle::Classes { stylesheet } => {
+                html::css_for_theme_with_class_style(&self.theme, CLASS_STYLE)
+                    .ok()
+                    .map(|css| (stylesheet.clone(), css))
+            }
         }
-        buffer
     }
 }
+
+// the line's newline is escaped text somewhere in the html, the renderer places its own
+fn without_newline(mut html: String) -> String {
+    if let Some(at) = html.rfind('\n') {
+        html.remove(at);
+    }
+    html
+}
@@ -3,7 +3,7 @@ mod highlight;
 use std::fmt::Display;
 
 pub use doctree::{render_page, render_summary};
-pub use highlight::{CodeHighlighter, NullHighligher};
+pub use highlight::{CodeHighlighter, HighlightStyle, NullHighligher, SyntectHighlighter};
 
 struct DisplayableOption<'a, T>
 where
@@ -24,3 +24,20 @@ where
         }
     }
 }
+
+// makes text safe to place in html, both between tags and inside of quoted attributes
+pub fn escape<S: AsRef<str>>(s: S) -> String {
+    let s = s.as_ref();
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,222 @@
+use std::{fs, path};
+
+use crate::{config, files, site};
+
+const CONFIG: &str = r#"site:
+  baseUrl: localhost:1312
+  https: never
+  template:
+    path: template
+
+content:
+  pages:
+    path: content/articles
+  include:
+    - path: content/static
+      as: static/
+
+output:
+  writeAsDirectories: true
+  highlighting:
+    # classes, inline or none
+    style: classes
+    theme: InspiredGitHub
+    stylesheet: highlight.css
+  files:
+    path: public
+
+transformers:
+  use: default
+  include:
+    - dates
+    - sitemap
+"#;
+
+const PAGE_TEMPLATE: &str = r#"<link rel="stylesheet" href="/highlight.css">
+
+<h1>{{ page.title }}</h1>
+{% if page.date %}
+<time datetime="{{ page.date }}">{{ page.date|date }}</time>
+{% endif %}
+
+{% if tags %}
+<ul class="tags">
+  {% for tag in tags %}
+  <li>{{ tag }}</li>
+  {% endfor %}
+</ul>
+{% endif %}
+
+{% if toc_html %}
+<nav class="toc">{{ toc_html }}</nav>
+{% endif %}
+
+{{ page.content }}
+"#;
+
+const TAGS_TEMPLATE: &str = r#"<h1>Tags</h1>
+
+<ul class="tags">
+{% for bucket in archive %}
+  <li>
+    {% if bucket.url %}<a href="{{ bucket.url }}">{{ bucket.title }}</a>{% else %}{{ bucket.title }}{% endif %}
+    ({{ bucket.count }})
+  </li>
+{% endfor %}
+</ul>
+"#;
+
+const TAG_TEMPLATE: &str = r#"<h1>{{ bucket.title }}</h1>
+
+<ul>
+{% for page in bucket.pages %}
+  <li><a href="{{ page.url }}">{{ page.title }}</a> {% if page.date %}{{ page.date|date }}{% endif %}</li>
+{% endfor %}
+</ul>
+
+{% if paginator.next or paginator.prev %}
+<nav class="pagination">
+  {% if paginator.prev %}<a href="{{ paginator.prev }}">Previous</a>{% endif %}
+  {% if paginator.next %}<a href="{{ paginator.next }}">Next</a>{% endif %}
+</nav>
+{% endif %}
+"#;
+
+const ARCHIVE_TEMPLATE: &str = r#"<h1>Archive</h1>
+
+<ul>
+{% for bucket in archive %}
+  <li>
+    <a href="{{ bucket.url }}">{{ bucket.title }}</a>
+    <ul>
+    {% for page in bucket.pages %}
+      <li><a href="{{ page.url }}">{{ page.title }}</a> {{ page.date|date }}</li>
+    {% endfor %}
+    </ul>
+  </li>
+{% endfor %}
+</ul>
+"#;
+
+const ARCHIVE_PERIOD_TEMPLATE: &str = r#"<h1>{{ bucket.title }}</h1>
+
+<ul>
+{% for page in bucket.pages %}
+  <li><a href="{{ page.url }}">{{ page.title }}</a> {{ page.date|date }}</li>
+{% endfor %}
+</ul>
+
+{% if paginator.next or paginator.prev %}
+<nav class="pagination">
+  {% if paginator.prev %}<a href="{{ paginator.prev }}">Previous</a>{% endif %}
+  {% if paginator.next %}<a href="{{ paginator.next }}">Next</a>{% endif %}
+</nav>
+{% endif %}
+"#;
+
+const SECTION_TEMPLATE: &str = r#"<h1>{{ page.title }}</h1>
+
+{{ page.content }}
+
+{% if section.sections %}
+<ul class="sections">
+  {% for sub in section.sections %}
+  <li><a href="{{ sub.url }}">{{ sub.title }}</a></li>
+  {% endfor %}
+</ul>
+{% endif %}
+
+<ul>
+  {% for page in section.pages %}
+  <li><a href="{{ page.url }}">{{ page.title }}</a></li>
+  {% endfor %}
+</ul>
+"#;
+
+const INDEX_TEMPLATE: &str = r#"<h1>Most Recent</h1>
+
+<ul>
+{% for page in pages %}
+  <li><a href="{{ page.url }}">{{ page.title }}</a> {{ page.date|date }}</li>
+{% endfor %}
+</ul>
+
+<nav class="pagination">
+  {% if paginator.prev %}<a href="{{ paginator.prev }}">Newer</a>{% endif %}
+  {{ paginator.current }} / {{ paginator.total }}
+  {% if paginator.next %}<a href="{{ paginator.next }}">Older</a>{% endif %}
+</nav>
+"#;
+
+pub fn new_site<P: AsRef<path::Path>>(dir: P) -> crate::Result<()> {
+    let dir = dir.as_ref();
+    let config = dir.join("config.yaml");
+    if config.exists() {
+        return Err(Box::new(site::SiteError::AlreadyOccupied(
+            files::Path::parse(config).unwrap(),
+        )));
+    }
+
+    for sub in ["content/articles", "content/static", "template"] {
+        fs::create_dir_all(dir.join(sub))?;
+    }
+
+    fs::write(config, CONFIG)?;
+    fs::write(dir.join("template/page.html"), PAGE_TEMPLATE)?;
+    fs::write(dir.join("template/tags.html"), TAGS_TEMPLATE)?;
+    fs::write(dir.join("template/tag.html"), TAG_TEMPLATE)?;
+    fs::write(dir.join("template/archive.html"), ARCHIVE_TEMPLATE)?;
+    fs::write(
+        dir.join("template/archive_period.html"),
+        ARCHIVE_PERIOD_TEMPLATE,
+    )?;
+    fs::write(dir.join("template/section.html"), SECTION_TEMPLATE)?;
+    fs::write(dir.join("template/index.html"), INDEX_TEMPLATE)?;
+    write_post(
+        &unsafe { files::DirPath::new(dir.join("content/articles")) },
+        "Hello World",
+    )?;
+    Ok(())
+}
+
+pub fn new_post<S: AsRef<str>>(content: &files::DirPath, title: S) -> crate::Result<()> {
+    fs::create_dir_all(content)?;
+    let path = write_post(content, title)?;
+    println!("created {}", path);
+    Ok(())
+}
+
+fn write_post<S: AsRef<str>>(content: &files::DirPath, title: S) -> crate::Result<files::FilePath> {
+    let title = title.as_ref();
+    let slug = slug(title);
+    // `.md` would be a hidden file that's never loaded
+    if slug.is_empty() {
+        return Err(Box::new(config::Error::Invalid(
+            "title".to_owned(),
+            format!("{:?} has nothing to name the post's file after", title),
+        )));
+    }
+    let path = unsafe { files::FilePath::new(content.join(format!("{}.md", slug))) };
+    if path.exists() {
+        return Err(Box::new(site::SiteError::AlreadyOccupied(path.into())));
+    }
+
+    fs::write(
+        &path,
+        format!(
+            "---\ntitle: \"{}\"\ndate: {}\ntags: []\n---\n\n",
+            title.replace('\\', "\\\\").replace('"', "\\\""),
+            chrono::Local::now().format("%Y-%m-%d"),
+        ),
+    )?;
+    Ok(path)
+}
+
+fn slug(title: &str) -> String {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
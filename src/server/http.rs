@@ -0,0 +1,106 @@
+use std::io::{self, BufRead, Write};
+
+pub struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+}
+
+impl Request {
+    // only the request line matters to us, headers are read and dropped
+    pub fn read<R: BufRead>(reader: &mut R) -> io::Result<Option<Request>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(m), Some(t)) => (m.to_owned(), t),
+            _ => return Ok(None),
+        };
+        let path = percent_decode(target.split(['?', '#']).next().unwrap_or("/"));
+
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+        }
+
+        Ok(Some(Request { method, path }))
+    }
+}
+
+pub struct Response {
+    status: u16,
+    reason: &'static str,
+    mime: &'static str,
+    body: Vec<u8>,
+    include_body: bool,
+}
+
+impl Response {
+    pub fn ok(mime: &'static str, body: Vec<u8>) -> Response {
+        Self {
+            status: 200,
+            reason: "OK",
+            mime,
+            body,
+            include_body: true,
+        }
+    }
+
+    pub fn status(status: u16, reason: &'static str) -> Response {
+        Self {
+            status,
+            reason,
+            mime: "text/plain; charset=utf-8",
+            body: format!("{status} {reason}\n").into_bytes(),
+            include_body: true,
+        }
+    }
+
+    pub fn head(mut self) -> Response {
+        self.include_body = false;
+        self
+    }
+
+    pub fn send<W: Write>(self, w: &mut W) -> crate::Result<()> {
+        write!(
+            w,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.reason,
+            self.mime,
+            self.body.len()
+        )?;
+        if self.include_body {
+            w.write_all(&self.body)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+fn percent_decode(raw: &str) -> String {
+    let bytes = raw.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let hex = bytes
+            .get(idx + 1..idx + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[idx], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                idx += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                idx += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
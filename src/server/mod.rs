@@ -0,0 +1,149 @@
+mod http;
+mod reload;
+
+use std::{
+    io::{BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use crate::writers;
+pub use reload::Reloader;
+
+const RELOAD_PATH: &str = "/__donter/reload";
+const RELOAD_SCRIPT: &str = r#"<script>
+(function () {
+  var source = new EventSource("/__donter/reload");
+  source.onmessage = function () { window.location.reload(); };
+})();
+</script>
+"#;
+
+pub struct Options {
+    pub(crate) listen: String,
+    pub(crate) reload: bool,
+}
+
+// serves whatever is currently in the site, every connection gets its own thread
+pub struct Server {
+    opts: Options,
+    site: writers::ServedSite,
+    reloader: Arc<Reloader>,
+}
+
+impl Server {
+    pub fn new(opts: Options, site: writers::ServedSite) -> Server {
+        Self {
+            opts,
+            site,
+            reloader: Arc::new(Reloader::default()),
+        }
+    }
+
+    pub fn reloader(&self) -> Arc<Reloader> {
+        self.reloader.clone()
+    }
+
+    pub fn start(self) -> crate::Result<thread::JoinHandle<()>> {
+        let listener = TcpListener::bind(&self.opts.listen)?;
+        println!("serving on http://{}", self.opts.listen);
+        let server = Arc::new(self);
+        Ok(thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Err(_) => continue,
+                    Ok(s) => s,
+                };
+                let server = server.clone();
+                thread::spawn(move || {
+                    if let Err(e) = server.handle(stream) {
+                        eprintln!("error while serving: {}", e);
+                    }
+                });
+            }
+        }))
+    }
+
+    fn handle(&self, stream: TcpStream) -> crate::Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let request = match http::Request::read(&mut reader)? {
+            None => return Ok(()),
+            Some(r) => r,
+        };
+        let mut stream = stream;
+
+        if request.method != "GET" && request.method != "HEAD" {
+            return http::Response::status(405, "Method Not Allowed").send(&mut stream);
+        }
+
+        if self.opts.reload && request.path == RELOAD_PATH {
+            return self.reload_events(stream);
+        }
+
+        let response = match self.lookup(&request.path) {
+            None => http::Response::status(404, "Not Found"),
+            Some(resp) => resp,
+        };
+
+        if request.method == "HEAD" {
+            response.head().send(&mut stream)
+        } else {
+            response.send(&mut stream)
+        }
+    }
+
+    fn lookup(&self, path: &str) -> Option<http::Response> {
+        let site = self.site.read().unwrap();
+        let candidates = if path.ends_with('/') {
+            vec![format!("{path}index.html")]
+        } else {
+            vec![
+                path.to_owned(),
+                format!("{path}/index.html"),
+                format!("{path}.html"),
+            ]
+        };
+
+        let served = candidates.iter().find_map(|c| site.get(c))?;
+        let mut content = served.content.clone();
+        if self.opts.reload && served.mime.starts_with("text/html") {
+            inject_script(&mut content);
+        }
+        Some(http::Response::ok(served.mime, content))
+    }
+
+    // server sent events, a message is pushed every time the site is rebuilt
+    fn reload_events(&self, mut stream: TcpStream) -> crate::Result<()> {
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )?;
+        stream.flush()?;
+
+        let mut seen = self.reloader.generation();
+        loop {
+            let current = self.reloader.wait(seen, Duration::from_secs(15));
+            let msg: &[u8] = if current != seen {
+                b"data: reload\n\n"
+            } else {
+                // keep alive, also how we find out the browser went away
+                b": ping\n\n"
+            };
+            seen = current;
+            if stream.write_all(msg).and_then(|_| stream.flush()).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn inject_script(content: &mut Vec<u8>) {
+    let needle = b"</body>";
+    let at = content
+        .windows(needle.len())
+        .rposition(|w| w.eq_ignore_ascii_case(needle))
+        .unwrap_or(content.len());
+    content.splice(at..at, RELOAD_SCRIPT.bytes());
+}
@@ -0,0 +1,33 @@
+use std::{
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+// every rebuild bumps the generation, anyone waiting on it is woken up
+#[derive(Default)]
+pub struct Reloader {
+    generation: Mutex<u64>,
+    changed: Condvar,
+}
+
+impl Reloader {
+    pub fn generation(&self) -> u64 {
+        *self.generation.lock().unwrap()
+    }
+
+    pub fn notify(&self) {
+        let mut generation = self.generation.lock().unwrap();
+        *generation += 1;
+        self.changed.notify_all();
+    }
+
+    // returns the current generation once it moves past `seen` or the timeout elapses
+    pub fn wait(&self, seen: u64, timeout: Duration) -> u64 {
+        let generation = self.generation.lock().unwrap();
+        let (generation, _) = self
+            .changed
+            .wait_timeout_while(generation, timeout, |g| *g == seen)
+            .unwrap();
+        *generation
+    }
+}
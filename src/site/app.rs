@@ -1,3 +1,4 @@
+use super::cache;
 use super::initializer;
 use super::rendered::RenderingSite;
 use super::IncludedAsset;
@@ -5,94 +6,179 @@ use super::Loader;
 use super::Processor;
 use super::RenderedPageMetadata;
 use super::RenderedSite;
+use super::RenderingPage;
+use super::Writer;
 use crate::content;
 use crate::files;
 use crate::jinja;
 use crate::render::render_page;
 use crate::render::render_summary;
+use crate::render::CodeHighlighter;
+use rayon::prelude::*;
 use std::borrow::Cow;
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::Arc;
+
+// how many pages each thread renders before the batch is handed to the writer
+const RENDER_BATCH_PER_THREAD: usize = 8;
+
+type RenderedEntry<'site> = (
+    RenderedPageMetadata<'site>,
+    RenderingPage<'site, 'site>,
+    Vec<u8>,
+);
 
 pub struct AppConfig {
-    asset_base: files::DirPath,
+    pages: files::Filter,
 }
 
 pub struct App<'a> {
     loaders: Vec<Box<dyn Loader + 'a>>,
     processors: Vec<Box<dyn Processor + 'a>>,
-    renderer: Rc<RefCell<minijinja::Environment<'a>>>,
+    renderer: Arc<minijinja::Environment<'a>>,
     config: AppConfig,
     linker: super::Linker<'a>,
+    cache: Option<super::Cache>,
+    highlighter: Box<dyn CodeHighlighter + 'a>,
+    anchors: bool,
 }
 
 impl<'env> App<'env> {
     pub fn create(mut site: super::Builder<'env>) -> crate::Result<App<'env>> {
         let mut renderer = minijinja::Environment::new();
         let mut loaders = Default::default();
-
-        {
-            let mut builder = initializer::Initializer {
-                renderer: jinja::Builder::new(&mut renderer),
-                loaders: &mut loaders,
-            };
-
-            for processor in site.processors.iter_mut() {
-                processor.initialize(&mut builder)?;
-            }
-        }
+        Self::initialize(&mut site.processors, &mut renderer, &mut loaders)?;
 
         Ok(App {
             linker: super::Linker::new(site.linker_opts),
+            cache: site.cache,
+            highlighter: site.highlighter,
+            anchors: site.anchors,
             processors: site.processors,
             loaders,
-            renderer: Rc::new(RefCell::new(renderer)),
-            config: AppConfig {
-                asset_base: unsafe { files::DirPath::new("assets") },
-            },
+            renderer: Arc::new(renderer),
+            config: AppConfig { pages: site.pages },
         })
     }
 
+    // runs initialization again from scratch so template and loader changes are picked up
+    // without recreating the whole application
+    pub fn reload(&mut self) -> crate::Result<()> {
+        let mut renderer = minijinja::Environment::new();
+        let mut loaders = Default::default();
+        Self::initialize(&mut self.processors, &mut renderer, &mut loaders)?;
+        self.renderer = Arc::new(renderer);
+        self.loaders = loaders;
+        Ok(())
+    }
+
+    fn initialize(
+        processors: &mut [Box<dyn Processor + 'env>],
+        renderer: &mut minijinja::Environment<'env>,
+        loaders: &mut Vec<Box<dyn Loader>>,
+    ) -> crate::Result<()> {
+        let mut builder = initializer::Initializer {
+            renderer: jinja::Builder::new(renderer),
+            loaders,
+        };
+
+        for processor in processors.iter_mut() {
+            processor.initialize(&mut builder)?;
+        }
+
+        Ok(())
+    }
+
+    // pages are parsed concurrently, processors see them one at a time in path order
     pub fn load(
         &mut self,
         path: &files::DirPath,
         corpus: &mut content::Corpus,
     ) -> crate::Result<()> {
-        for path in files::Walker::walk(path, files::RecursionBehavior::Dont) {
-            for loader in self.loaders.iter_mut() {
-                if loader.accept(&path)? {
-                    let mut builder = corpus.make_page(path.clone());
-                    loader.load(Box::new(std::fs::File::open(&path)?), &mut builder)?;
-                    for processor in self.processors.iter_mut() {
-                        processor.page_loading(&mut builder)?;
-                    }
-                    corpus.add_page(builder)?;
-                    break;
-                }
+        let mut paths: Vec<_> = files::Walker::filtered(
+            path,
+            files::RecursionBehavior::Recurse,
+            self.config.pages.clone(),
+        )
+        .collect();
+        paths.sort();
+
+        let mut pending = Vec::with_capacity(paths.len());
+        for file in paths {
+            if let Some(loader) = self.accepting(&file)? {
+                let section = content::Section::of(path, &file);
+                let mut builder = corpus.make_page(file);
+                builder.in_section(section);
+                pending.push((loader, builder));
             }
         }
+
+        let loaded = pending
+            .into_par_iter()
+            .map(|(loader, builder)| self.load_page(loader, builder))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        // cached or not every page goes through the hooks, the cache only holds what the loader made
+        for page in loaded {
+            let mut builder = content::PageBuilder::from(page);
+            for processor in self.processors.iter_mut() {
+                processor.page_loading(&mut builder)?;
+            }
+            corpus.add_page(builder)?;
+        }
         Ok(())
     }
 
+    fn accepting(&self, path: &files::FilePath) -> crate::Result<Option<usize>> {
+        for (idx, loader) in self.loaders.iter().enumerate() {
+            if loader.accept(path)? {
+                return Ok(Some(idx));
+            }
+        }
+        Ok(None)
+    }
+
+    fn load_page(
+        &self,
+        loader: usize,
+        mut builder: content::PageBuilder,
+    ) -> crate::Result<content::Page> {
+        let source = std::fs::read(&builder.filepath)?;
+        let checksum = cache::checksum(&source);
+
+        if let Some((meta, content)) = self
+            .cache
+            .as_ref()
+            .and_then(|c| c.page(&builder.filepath, checksum))
+        {
+            return Ok(content::Page {
+                id: builder.id,
+                meta,
+                content,
+            });
+        }
+
+        builder.checksum = checksum;
+        self.loaders[loader].load(Box::new(std::io::Cursor::new(source)), &mut builder)?;
+        let page = builder.build()?;
+        if let Some(cache) = &self.cache {
+            cache.store_page(&page)?;
+        }
+        Ok(page)
+    }
+
+    // renders the page's contents but leaves placing it into the site to the caller so this can
+    // run on any thread
     pub fn render_page<'rendering, 'site>(
         &'rendering self,
         page: &'site content::Page,
-        site: &mut RenderingSite<'rendering, 'site, 'env>,
-    ) -> crate::Result<()>
+        mut rendering: RenderingPage<'site, 'site>,
+        renderer: &jinja::Renderer<'rendering, 'env>,
+        dependencies: u64,
+    ) -> crate::Result<RenderedEntry<'site>>
     where
         'env: 'site,
         'site: 'rendering,
     {
-        let mut rendering = site.page(&page.meta.tpl_name);
-
-        rendering.values().merge(minijinja::context! {
-          page => minijinja::context!{
-            content => minijinja::Value::from_safe_string(render_page(&page.content)),
-            title => page.meta.title,
-            date => page.meta.when
-          }
-        });
-
         for processor in self.processors.iter() {
             processor.page_rendering(page, &mut rendering)?;
         }
@@ -100,19 +186,62 @@ impl<'env> App<'env> {
         let meta = RenderedPageMetadata {
             origin: Some(page.id.clone()),
             title: Cow::Borrowed(&page.meta.title),
-            url: Cow::Owned(self.linker.slug(&page.meta.origin)),
+            url: Cow::Owned(self.linker.slug(&page.meta)),
             summary: page.meta.summary.as_ref().map(|summ| {
                 render_summary(
                     summ.children(),
                     &page.content.footnotes,
                     &page.content.hrefs,
+                    self.highlighter.as_ref(),
                 )
             }),
         };
 
-        site.render_page(meta, rendering)?;
+        // the page's own source and whatever it included covers its content, title and date --
+        // everything else the template sees was contributed by processors
+        let key = match &self.cache {
+            None => None,
+            Some(cache) => {
+                let values = cache::canonical_json(rendering.values())?;
+                let key = cache::checksum(&(
+                    page.meta.checksum,
+                    &page.meta.includes,
+                    dependencies,
+                    values,
+                ));
+                if let Some(content) = cache.rendered(key) {
+                    return Ok((meta, rendering, content));
+                }
+                Some(key)
+            }
+        };
+
+        // everything from the frontmatter, including keys nothing else knows about
+        let frontmatter: minijinja::Value = page
+            .meta
+            .meta
+            .iter()
+            .map(|(k, v)| (k.as_str(), minijinja::Value::from(v)))
+            .collect();
+        rendering.values().merge(minijinja::context! {
+          page => minijinja::context!{
+            content => minijinja::Value::from_safe_string(render_page(
+                &page.content,
+                self.highlighter.as_ref(),
+                self.anchors
+            )),
+            title => page.meta.title,
+            date => page.meta.when,
+            meta => frontmatter
+          }
+        });
 
-        Ok(())
+        let content = rendering.render(renderer)?;
+        if let (Some(cache), Some(key)) = (&self.cache, key) {
+            cache.store_rendered(key, &content)?;
+        }
+
+        Ok((meta, rendering, content))
     }
 
     pub fn process(&mut self, corpus: &mut content::Corpus) -> crate::Result<()> {
@@ -123,9 +252,11 @@ impl<'env> App<'env> {
         Ok(())
     }
 
+    // pages are rendered a batch at a time and handed to the writer as each batch finishes
     pub fn render<'render, 'site>(
         &'render self,
         corpus: &'site content::Corpus,
+        writer: &'render mut dyn Writer,
     ) -> crate::Result<RenderedSite<'site>>
     where
         'env: 'site,
@@ -135,33 +266,169 @@ impl<'env> App<'env> {
         for processor in self.processors.iter() {
             processor.global_render_context(&mut globals)?;
         }
-        let mut site = RenderingSite::new(jinja::Renderer::new(&self.renderer, globals));
+        let dependencies = self.dependencies(&globals)?;
+        let mut site = RenderingSite::new(
+            jinja::Renderer::new(&self.renderer, globals),
+            writer,
+            self.linker.base().clone(),
+        );
+
+        // sorted so ids and output don't depend on hash map ordering
+        let mut pages: Vec<_> = corpus.pages().collect();
+        pages.sort_by(|a, b| a.meta.origin.cmp(&b.meta.origin));
+        let (sections, articles): (Vec<_>, Vec<_>) = pages
+            .iter()
+            .partition(|p| p.meta.kind == content::PageKind::Section);
+
+        self.render_pages(&articles, &mut site, dependencies, |_, _| None)?;
+
+        // section listings link to the sections below them so the deepest are rendered first
+        let mut depths: Vec<_> = sections.iter().map(|p| p.meta.section.depth()).collect();
+        depths.sort_by(|a, b| b.cmp(a));
+        depths.dedup();
+        for depth in depths {
+            let sections: Vec<_> = sections
+                .iter()
+                .copied()
+                .filter(|p| p.meta.section.depth() == depth)
+                .collect();
+            self.render_pages(&sections, &mut site, dependencies, |section, site| {
+                Some(section_context(section, &pages, site))
+            })?;
+        }
 
         for entry in corpus.entries() {
-            match entry {
-                content::CorpusEntry::Page(p) => {
-                    self.render_page(&p, &mut site)?;
-                }
-                content::CorpusEntry::StaticAsset(asset) => {
-                    site.add_asset(IncludedAsset::create(
-                        asset,
-                        self.config.asset_base.join(asset),
-                    ));
-                }
+            if let content::CorpusEntry::StaticAsset(asset) = entry {
+                site.add_asset(IncludedAsset::create(asset.source().clone(), unsafe {
+                    files::FilePath::new(asset.destination())
+                }))?;
             }
         }
 
+        if let Some((path, css)) = self.highlighter.stylesheet() {
+            site.add_file(path, css.into_bytes())?;
+        }
+
         for processor in self.processors.iter() {
-            processor.site_rendering(&corpus, &mut site)?;
+            processor.site_rendering(corpus, &mut site)?;
         }
 
         Ok(site.render())
     }
 
+    // renders a batch at a time on the thread pool, `context` contributes anything extra the page
+    // needs before processors see it
+    fn render_pages<'render, 'site, F>(
+        &'render self,
+        pages: &[&'site content::Page],
+        site: &mut RenderingSite<'render, 'site, 'env>,
+        dependencies: u64,
+        context: F,
+    ) -> crate::Result<()>
+    where
+        'env: 'site,
+        'site: 'render,
+        F: Fn(&content::Page, &RenderingSite<'render, 'site, 'env>) -> Option<minijinja::Value>,
+    {
+        let batch = rayon::current_num_threads() * RENDER_BATCH_PER_THREAD;
+        for pages in pages.chunks(batch) {
+            let mut pending = Vec::with_capacity(pages.len());
+            for page in pages {
+                let extra = context(page, site);
+                let mut rendering = site.page(&page.meta.tpl_name);
+                if let Some(extra) = extra {
+                    rendering.values().merge(extra);
+                }
+                pending.push((*page, rendering));
+            }
+
+            let renderer = site.renderer();
+            let rendered = pending
+                .into_par_iter()
+                .map(|(page, rendering)| self.render_page(page, rendering, renderer, dependencies))
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            for (meta, rendering, content) in rendered {
+                site.add_rendered(meta, rendering, content)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn finalize(&mut self) -> crate::Result<()> {
         for processor in self.processors.iter_mut() {
             processor.finalize()?;
         }
+        if let Some(cache) = &self.cache {
+            cache.prune()?;
+        }
         Ok(())
     }
+
+    // everything shared by all pages that can change what a page renders to
+    fn dependencies(&self, globals: &jinja::RenderContext) -> crate::Result<u64> {
+        let mut templates: Vec<_> = self
+            .renderer
+            .templates()
+            .map(|(name, tpl)| (name, tpl.source().to_owned()))
+            .collect();
+        templates.sort();
+        Ok(cache::checksum(&(
+            templates,
+            cache::canonical_json(globals)?,
+            self.anchors,
+        )))
+    }
+}
+
+#[derive(serde::Serialize)]
+struct SectionEntry<'a> {
+    title: &'a str,
+    url: String,
+    date: Option<content::Date>,
+    summary: Option<&'a str>,
+}
+
+// a section lists the articles directly inside of it and the sections immediately below it
+fn section_context(
+    section: &content::Page,
+    pages: &[&content::Page],
+    site: &RenderingSite,
+) -> minijinja::Value {
+    let here = &section.meta.section;
+    let mut articles = Vec::new();
+    let mut sections = Vec::new();
+
+    for page in pages {
+        let rendered = match site.get_by_origin(&page.id) {
+            None => continue,
+            Some(r) => r,
+        };
+        let entry = SectionEntry {
+            title: &rendered.title,
+            url: super::href(site.base_url(), &rendered.url.to_string()),
+            date: page.meta.when,
+            summary: rendered.summary.as_deref(),
+        };
+        match page.meta.kind {
+            content::PageKind::Article if &page.meta.section == here => articles.push(entry),
+            content::PageKind::Section if page.meta.section.parent().as_ref() == Some(here) => {
+                sections.push(entry)
+            }
+            _ => {}
+        }
+    }
+
+    // newest first, undated pages go last
+    articles.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.title.cmp(b.title)));
+    sections.sort_by(|a, b| a.title.cmp(b.title));
+
+    minijinja::context! {
+        section => minijinja::context! {
+            path => here.to_string(),
+            name => here.name(),
+            pages => articles,
+            sections => sections,
+        }
+    }
 }
@@ -1,33 +1,25 @@
-use super::RenderedPage;
 use crate::files;
 
+// a single file copied into the output as is
 pub struct IncludedAsset {
-    src: files::Path,
-    dest: files::Path,
+    src: files::FilePath,
+    dest: files::FilePath,
 }
 
 impl IncludedAsset {
-    pub fn create<S: Into<files::Path>, D: Into<files::Path>>(src: S, dest: D) -> Self {
-        Self {
-            src: src.into(),
-            dest: dest.into(),
-        }
+    pub fn create(src: files::FilePath, dest: files::FilePath) -> Self {
+        Self { src, dest }
     }
 
     pub fn read(self) -> crate::Result<impl std::io::Read> {
         Ok(std::fs::File::open(self.src)?)
     }
 
-    pub fn source(&self) -> &files::Path {
+    pub fn source(&self) -> &files::FilePath {
         &self.src
     }
 
-    pub fn destination(&self) -> &files::Path {
+    pub fn destination(&self) -> &files::FilePath {
         &self.dest
     }
 }
-
-pub enum Writable<'a> {
-    Page(RenderedPage<'a>),
-    Asset(IncludedAsset),
-}
@@ -1,10 +1,15 @@
 use super::App;
+use crate::{files, render};
 
 use super::Processor;
 
 pub struct Builder<'a> {
     pub(crate) processors: Vec<Box<dyn Processor + 'a>>,
     pub(crate) linker_opts: super::LinkerOptions<'a>,
+    pub(crate) cache: Option<super::Cache>,
+    pub(crate) pages: files::Filter,
+    pub(crate) highlighter: Box<dyn render::CodeHighlighter + 'a>,
+    pub(crate) anchors: bool,
 }
 
 impl<'a> Builder<'a> {
@@ -12,6 +17,10 @@ impl<'a> Builder<'a> {
         Self {
             processors: vec![],
             linker_opts: Default::default(),
+            cache: None,
+            pages: Default::default(),
+            highlighter: Box::new(render::NullHighligher),
+            anchors: false,
         }
     }
 
@@ -20,6 +29,28 @@ impl<'a> Builder<'a> {
         self
     }
 
+    pub fn cache(mut self, cache: Option<super::Cache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    // what's skipped when walking for pages, static files are filtered as they're included
+    pub fn page_filter(mut self, pages: files::Filter) -> Self {
+        self.pages = pages;
+        self
+    }
+
+    pub fn highlighter(mut self, highlighter: Box<dyn render::CodeHighlighter + 'a>) -> Self {
+        self.highlighter = highlighter;
+        self
+    }
+
+    // self links next to every heading
+    pub fn anchors(mut self, anchors: bool) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
     pub fn with_when<F, P>(mut self, cond: bool, factory: F) -> Self
     where
         P: Processor + 'a,
@@ -0,0 +1,300 @@
+use std::{
+    collections::HashSet,
+    fs,
+    hash::{Hash, Hasher},
+    path,
+    sync::Mutex,
+};
+
+use crate::{content, files};
+
+// bump whenever the shape of anything stored in the cache changes
+const CACHE_VERSION: u64 = 8;
+
+// parsed pages keyed by their source path and rendered output keyed by everything that went into
+// rendering it, entries that aren't touched during a build are removed by `prune`
+pub struct Cache {
+    dir: path::PathBuf,
+    touched: Mutex<HashSet<path::PathBuf>>,
+}
+
+#[derive(serde::Serialize)]
+struct CachedPageRef<'a> {
+    meta: &'a content::PageMetadata,
+    content: &'a content::PageContents,
+}
+
+#[derive(serde::Deserialize)]
+struct CachedPage {
+    meta: content::PageMetadata,
+    content: content::PageContents,
+}
+
+// checksums outlive the process that made them so they can't come from the std hasher, which
+// is free to change between builds
+pub fn checksum<H: Hash + ?Sized>(h: &H) -> u64 {
+    let mut hasher = Fnv::default();
+    h.hash(&mut hasher);
+    hasher.finish()
+}
+
+// json with every object's keys in order, for hashing values that were built from hash maps.
+// going through a `Value` is enough since serde_json's maps are sorted
+pub fn canonical_json<T: serde::Serialize + ?Sized>(value: &T) -> crate::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&serde_json::to_value(value)?)?)
+}
+
+// 64 bit fnv-1a
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+impl Cache {
+    // anything built under a different fingerprint is thrown away
+    pub fn open<P: Into<path::PathBuf>>(dir: P, fingerprint: u64) -> crate::Result<Cache> {
+        let dir = dir.into();
+        let fingerprint = format!("{:x}", checksum(&(CACHE_VERSION, fingerprint)));
+        let marker = dir.join("fingerprint");
+        let current = fs::read_to_string(&marker).ok();
+
+        if current.as_deref() != Some(fingerprint.as_str()) && dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+
+        fs::create_dir_all(dir.join("pages"))?;
+        fs::create_dir_all(dir.join("rendered"))?;
+        fs::write(marker, fingerprint)?;
+
+        Ok(Self {
+            dir,
+            touched: Default::default(),
+        })
+    }
+
+    pub fn page(
+        &self,
+        origin: &files::FilePath,
+        checksum: u64,
+    ) -> Option<(content::PageMetadata, content::PageContents)> {
+        let path = self.page_path(origin);
+        let raw = fs::read(&path).ok()?;
+        let cached: CachedPage = serde_json::from_slice(&raw).ok()?;
+        if cached.meta.checksum != checksum {
+            return None;
+        }
+        let stale = cached
+            .meta
+            .includes
+            .iter()
+            .any(|(path, sum)| fs::read(path).map(|c| self::checksum(&c)).ok() != Some(*sum));
+        if stale {
+            return None;
+        }
+        self.touch(path);
+        Some((cached.meta, cached.content))
+    }
+
+    pub fn store_page(&self, page: &content::Page) -> crate::Result<()> {
+        let path = self.page_path(&page.meta.origin);
+        let cached = CachedPageRef {
+            meta: &page.meta,
+            content: &page.content,
+        };
+        fs::write(&path, serde_json::to_vec(&cached)?)?;
+        self.touch(path);
+        Ok(())
+    }
+
+    pub fn rendered(&self, key: u64) -> Option<Vec<u8>> {
+        let path = self.rendered_path(key);
+        let content = fs::read(&path).ok()?;
+        self.touch(path);
+        Some(content)
+    }
+
+    pub fn store_rendered(&self, key: u64, content: &[u8]) -> crate::Result<()> {
+        let path = self.rendered_path(key);
+        fs::write(&path, content)?;
+        self.touch(path);
+        Ok(())
+    }
+
+    // drops everything that wasn't used since the last prune
+    pub fn prune(&self) -> crate::Result<()> {
+        let mut touched = self.touched.lock().unwrap();
+        for sub in ["pages", "rendered"] {
+            for entry in fs::read_dir(self.dir.join(sub))? {
+                let path = entry?.path();
+                if !touched.contains(&path) {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+        touched.clear();
+        Ok(())
+    }
+
+    fn touch(&self, path: path::PathBuf) {
+        self.touched.lock().unwrap().insert(path);
+    }
+
+    fn page_path(&self, origin: &files::FilePath) -> path::PathBuf {
+        self.dir
+            .join("pages")
+            .join(format!("{:x}.json", checksum(origin.as_path())))
+    }
+
+    fn rendered_path(&self, key: u64) -> path::PathBuf {
+        self.dir.join("rendered").join(format!("{:x}", key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{files, ids};
+    use std::collections::HashMap;
+
+    // a fresh directory per test so they can run in parallel
+    fn scratch(name: &str) -> path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("donter-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn page(origin: &str, checksum: u64, includes: &[(&path::Path, u64)]) -> content::Page {
+        let mut ids = ids::IdPool::new(0);
+        let mut builder =
+            content::PageBuilder::new(ids.next(), unsafe { files::FilePath::new(origin) });
+        builder.with_title("Cached");
+        builder.checksum = checksum;
+        for (path, sum) in includes {
+            builder.depends_on(*path, *sum);
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn checksums_are_stable_fnv() {
+        // the published fnv-1a test vectors, anything else would invalidate caches between builds
+        let mut hasher = Fnv::default();
+        hasher.write(b"");
+        assert_eq!(hasher.finish(), 0xcbf29ce484222325);
+        let mut hasher = Fnv::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+
+        assert_eq!(checksum("page"), checksum("page"));
+        assert_ne!(checksum("page"), checksum("pages"));
+        assert_ne!(checksum(&(1u64, 2u64)), checksum(&(2u64, 1u64)));
+    }
+
+    #[test]
+    fn canonical_json_ignores_map_order() {
+        let mut forward = HashMap::new();
+        let mut backward = HashMap::new();
+        for key in 0..32 {
+            forward.insert(key.to_string(), key);
+        }
+        for key in (0..32).rev() {
+            backward.insert(key.to_string(), key);
+        }
+        assert_eq!(
+            canonical_json(&forward).unwrap(),
+            canonical_json(&backward).unwrap()
+        );
+        assert_eq!(
+            canonical_json(&serde_json::json!({"b": 1, "a": [2, 1]})).unwrap(),
+            br#"{"a":[2,1],"b":1}"#
+        );
+    }
+
+    #[test]
+    fn pages_come_back_until_their_source_changes() {
+        let dir = scratch("pages");
+        let cache = Cache::open(&dir, 1).unwrap();
+        let stored = page("content/a.md", 42, &[]);
+        cache.store_page(&stored).unwrap();
+
+        let origin = &stored.meta.origin.0;
+        let (meta, _) = cache.page(origin, 42).unwrap();
+        assert_eq!(meta.title, "Cached");
+        assert!(cache.page(origin, 43).is_none());
+        assert!(cache
+            .page(unsafe { &files::FilePath::new("content/b.md") }, 42)
+            .is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pages_are_stale_once_an_include_changes() {
+        let dir = scratch("includes");
+        let cache = Cache::open(&dir, 1).unwrap();
+        let include = dir.join("snippet.rs");
+        fs::write(&include, "fn main() {}").unwrap();
+        let stored = page(
+            "content/a.md",
+            7,
+            &[(&include, checksum(&b"fn main() {}".to_vec()))],
+        );
+        cache.store_page(&stored).unwrap();
+
+        let origin = &stored.meta.origin.0;
+        assert!(cache.page(origin, 7).is_some());
+        fs::write(&include, "fn main() { changed() }").unwrap();
+        assert!(cache.page(origin, 7).is_none());
+        fs::remove_file(&include).unwrap();
+        assert!(cache.page(origin, 7).is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_new_fingerprint_empties_the_cache() {
+        let dir = scratch("fingerprint");
+        let cache = Cache::open(&dir, 1).unwrap();
+        cache.store_rendered(5, b"<p>hi</p>").unwrap();
+        drop(cache);
+
+        let cache = Cache::open(&dir, 1).unwrap();
+        assert_eq!(cache.rendered(5).as_deref(), Some(&b"<p>hi</p>"[..]));
+        drop(cache);
+
+        let cache = Cache::open(&dir, 2).unwrap();
+        assert!(cache.rendered(5).is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn prune_drops_what_a_build_did_not_use() {
+        let dir = scratch("prune");
+        let cache = Cache::open(&dir, 1).unwrap();
+        cache.store_rendered(1, b"kept").unwrap();
+        cache.store_rendered(2, b"dropped").unwrap();
+        cache.prune().unwrap();
+
+        // the next build only asks for the first
+        assert!(cache.rendered(1).is_some());
+        cache.prune().unwrap();
+        assert!(cache.rendered(1).is_some());
+        assert!(cache.rendered(2).is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,8 +1,6 @@
 use super::asset::IncludedAsset;
-use super::asset::Writable;
 use super::initializer;
 use super::rendered::RenderedPage;
-use super::rendered::RenderedSite;
 use super::rendered::RenderingPage;
 use super::rendered::RenderingSite;
 use crate::content;
@@ -11,16 +9,17 @@ use crate::jinja;
 
 use crate::Result;
 
-pub trait Loader {
-    fn accept(&mut self, path: &files::FilePath) -> Result<bool>;
+// loaders and processors are shared across the threads pages are loaded and rendered on
+pub trait Loader: Send + Sync {
+    fn accept(&self, path: &files::FilePath) -> Result<bool>;
     fn load(
-        &mut self,
+        &self,
         content: Box<dyn std::io::Read>,
         builder: &mut content::PageBuilder,
     ) -> crate::Result<()>;
 }
 
-pub trait Processor {
+pub trait Processor: Send + Sync {
     // called as part of initializing the application
     fn initialize<'call, 'init>(
         &'call mut self,
@@ -51,7 +50,7 @@ pub trait Processor {
         Ok(())
     }
 
-    // called before the specific page is rendered
+    // called before the specific page is rendered, pages are rendered concurrently
     fn page_rendering<'render, 'site>(
         &self,
         page: &'site content::Page,
@@ -78,20 +77,8 @@ pub trait Processor {
     }
 }
 
+// pages and assets are written as soon as they're rendered
 pub trait Writer {
-    fn write(&mut self, site: RenderedSite<'_>) -> crate::Result<()> {
-        use Writable::*;
-
-        for (_, writable) in site.entries() {
-            match writable {
-                Page(page) => self.write_rendered_page(page)?,
-                Asset(asset) => self.write_static_asset(asset)?,
-            }
-        }
-
-        Ok(())
-    }
-
     fn flush(self: Box<Self>) -> crate::Result<()> {
         Ok(())
     }
@@ -1,24 +1,20 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, path::PathBuf};
 use url::Url;
 
-use crate::files;
+use crate::{content, files};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, serde::Serialize)]
 pub enum ArticleSlugStyle {
     Directory,
     Page,
 }
 
-pub enum ArticleSlugSource {
-    Filename,
-    Title,
-}
-
 pub struct Options<'a> {
     pub(crate) page_root: Option<files::DirPath>,
     pub(crate) site_base: Cow<'a, Url>,
-    pub(crate) slug_source: ArticleSlugSource,
     pub(crate) slug_style: ArticleSlugStyle,
+    // articles are placed under their section's path instead of directly in the page root
+    pub(crate) preserve_structure: bool,
 }
 
 impl<'a> Default for Options<'a> {
@@ -26,12 +22,26 @@ impl<'a> Default for Options<'a> {
         Self {
             page_root: None,
             site_base: Cow::Owned(Url::parse("http://localhost:1312").unwrap()),
-            slug_source: ArticleSlugSource::Filename,
             slug_style: ArticleSlugStyle::Page,
+            preserve_structure: false,
         }
     }
 }
 
+// `url`, relative to the site root, as it's linked to from outside the site
+pub fn absolute(base: &Url, url: &str) -> String {
+    let url = url.trim_start_matches('/');
+    base.join(url)
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| format!("{}{}", base, url))
+}
+
+// `url`, relative to the site root, as it's linked to from another page on the site. the base
+// url's path is kept so a site deployed under /blog/ links inside of it
+pub fn href(base: &Url, url: &str) -> String {
+    format!("{}{}", base.path(), url.trim_start_matches('/'))
+}
+
 pub struct Linker<'a> {
     opts: Options<'a>,
     // origin -> destination
@@ -39,14 +49,35 @@ pub struct Linker<'a> {
 }
 
 impl<'a> Linker<'a> {
-    pub fn new(opts: Options<'a>) -> Linker {
+    pub fn new(opts: Options<'a>) -> Linker<'a> {
         Self {
             opts,
             entries: Default::default(),
         }
     }
 
-    pub fn slug(&self, origin: &files::FilePath) -> files::FilePath {
+    pub fn base(&self) -> &Url {
+        &self.opts.site_base
+    }
+
+    pub fn slug(&self, page: &content::PageMetadata) -> files::FilePath {
+        // section pages always live in their section's directory, otherwise every section would
+        // be fighting over the same index.html
+        let stem = match page.kind {
+            content::PageKind::Section => page.section.as_path().join("index.html"),
+            content::PageKind::Article if self.opts.preserve_structure => {
+                page.section.as_path().join(self.article(&page.origin))
+            }
+            content::PageKind::Article => self.article(&page.origin),
+        };
+
+        match &self.opts.page_root {
+            None => unsafe { files::FilePath::new(stem) },
+            Some(pre) => unsafe { files::FilePath::new(pre.join(stem)) },
+        }
+    }
+
+    fn article(&self, origin: &files::FilePath) -> PathBuf {
         let ext = format!(
             ".{}",
             origin
@@ -65,16 +96,9 @@ impl<'a> Linker<'a> {
             .flatten()
             .unwrap();
 
-        let stem = unsafe {
-            files::FilePath::new(match self.opts.slug_style {
-                ArticleSlugStyle::Page => format!("{name}.html"),
-                ArticleSlugStyle::Directory => format!("{name}/index.html"),
-            })
-        };
-
-        match &self.opts.page_root {
-            None => stem,
-            Some(pre) => unsafe { files::FilePath::new(pre.join(stem)) },
-        }
+        PathBuf::from(match self.opts.slug_style {
+            ArticleSlugStyle::Page => format!("{name}.html"),
+            ArticleSlugStyle::Directory => format!("{name}/index.html"),
+        })
     }
 }
@@ -5,19 +5,21 @@ use crate::files;
 pub mod app;
 pub mod asset;
 pub mod builder;
+pub mod cache;
 pub mod exts;
 pub mod initializer;
 pub mod linker;
 pub mod rendered;
 pub use app::App;
 pub use asset::IncludedAsset;
-pub use asset::Writable;
 pub use builder::Builder;
+pub use cache::Cache;
 pub use exts::Loader;
 pub use exts::Processor;
 pub use exts::Writer;
 pub use initializer::Initializer;
-pub use linker::ArticleSlugSource;
+pub use linker::absolute;
+pub use linker::href;
 pub use linker::ArticleSlugStyle;
 pub use linker::Linker;
 pub use linker::Options as LinkerOptions;
@@ -31,6 +33,8 @@ pub use rendered::RenderingSite;
 #[derive(Debug)]
 pub enum SiteError {
     AlreadyOccupied(files::Path),
+    // a processor that was turned on has nothing to render with
+    MissingTemplate(String),
 }
 
 impl Display for SiteError {
@@ -39,6 +43,7 @@ impl Display for SiteError {
         write!(f, "SiteError::")?;
         match self {
             AlreadyOccupied(dst) => write!(f, "AlreadyOccupied({})", dst),
+            MissingTemplate(name) => write!(f, "MissingTemplate({})", name),
         }
     }
 }
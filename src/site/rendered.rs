@@ -1,13 +1,15 @@
 use super::IncludedAsset;
-use super::Writable;
+use super::Writer;
 use crate::content::CorpusEntry;
 use crate::files;
 use crate::ids;
 use crate::jinja;
 use std::borrow::Cow;
-use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
 
+// pages are handed to the writer as soon as they're rendered, only their metadata is kept around
+// for processors that need to know about other pages
 pub struct RenderingSite<'rendering, 'site, 'env>
 where
     'env: 'site,
@@ -16,6 +18,11 @@ where
     renderer: jinja::Renderer<'rendering, 'env>,
     ids: ids::IdPool<RenderedSite<'site>>,
     site: RenderedSite<'site>,
+    writer: &'rendering mut dyn Writer,
+    base: url::Url,
+    // every url written so far, two pages landing on the same one is an error rather than
+    // the later one silently replacing the earlier
+    written: HashSet<files::FilePath>,
 }
 
 impl<'rendering, 'site, 'env> RenderingSite<'rendering, 'site, 'env>
@@ -23,11 +30,18 @@ where
     'env: 'site,
     'site: 'rendering,
 {
-    pub fn new(renderer: jinja::Renderer<'rendering, 'env>) -> Self {
+    pub fn new(
+        renderer: jinja::Renderer<'rendering, 'env>,
+        writer: &'rendering mut dyn Writer,
+        base: url::Url,
+    ) -> Self {
         Self {
             ids: ids::IdPool::new(1),
             site: RenderedSite::new(),
             renderer,
+            writer,
+            base,
+            written: Default::default(),
         }
     }
 
@@ -35,6 +49,15 @@ where
         self.site
     }
 
+    pub fn renderer(&self) -> &jinja::Renderer<'rendering, 'env> {
+        &self.renderer
+    }
+
+    // where the site is deployed, links between pages go through `super::href` with it
+    pub fn base_url(&self) -> &url::Url {
+        &self.base
+    }
+
     pub fn page<'page>(&mut self, template: &'page str) -> RenderingPage<'page, 'site> {
         RenderingPage {
             id: self.ids.next(),
@@ -46,40 +69,80 @@ where
     pub fn render_page<'page, M: Into<RenderedPageMetadata<'site>>>(
         &'page mut self,
         meta: M,
-        page: RenderingPage<'page, 'site>,
+        mut page: RenderingPage<'page, 'site>,
     ) -> crate::Result<()>
     where
         'site: 'page,
     {
-        let rendered = self.renderer.render_template(&page.tpl, page.v)?;
+        let rendered = page.render(&self.renderer)?;
+        self.add_rendered(meta, page, rendered)
+    }
+
+    // places content that was rendered elsewhere, e.g. on another thread or pulled from the
+    // cache, into the site
+    pub fn add_rendered<M: Into<RenderedPageMetadata<'site>>>(
+        &mut self,
+        meta: M,
+        page: RenderingPage<'_, 'site>,
+        content: Vec<u8>,
+    ) -> crate::Result<()> {
+        let meta = meta.into();
+        self.claim(&meta.url)?;
         let page = RenderedPage {
             id: page.id,
-            content: VecDeque::from(rendered.into_bytes()),
-            meta: meta.into(),
+            content: VecDeque::from(content),
+            meta,
         };
         if let Some(ref origin) = page.meta.origin {
             self.site.origins.insert(origin.clone(), page.id.clone());
         }
-        self.site
-            .writables
-            .insert(page.id.clone(), Writable::Page(page));
+        self.site.pages.insert(page.id.clone(), page.meta.clone());
+        self.writer.write_rendered_page(page)
+    }
+
+    // generated output that isn't a page, e.g. a stylesheet, it isn't indexed with the pages
+    pub fn add_file(&mut self, url: files::FilePath, content: Vec<u8>) -> crate::Result<()> {
+        self.claim(&url)?;
+        self.writer.write_rendered_page(RenderedPage {
+            id: self.ids.next(),
+            content: VecDeque::from(content),
+            meta: RenderedPageMetadata {
+                origin: None,
+                title: Cow::Borrowed(""),
+                url: Cow::Owned(url),
+                summary: None,
+            },
+        })
+    }
+
+    // whether something has already been written to `url`
+    pub fn occupied(&self, url: &files::FilePath) -> bool {
+        self.written.contains(url)
+    }
+
+    fn claim(&mut self, url: &files::FilePath) -> crate::Result<()> {
+        if !self.written.insert(url.clone()) {
+            return Err(Box::new(super::SiteError::AlreadyOccupied(
+                files::Path::File(url.clone()),
+            )));
+        }
         Ok(())
     }
 
-    pub fn add_asset(&mut self, asset: IncludedAsset) {
-        self.site
-            .writables
-            .insert(self.ids.next(), Writable::Asset(asset));
+    pub fn add_asset(&mut self, asset: IncludedAsset) -> crate::Result<()> {
+        self.claim(asset.destination())?;
+        self.writer.write_static_asset(asset)
     }
 
-    pub fn get_by_origin<K>(&self, origin: K) -> Option<&Writable<'site>>
+    pub fn get_by_origin<K>(&self, origin: K) -> Option<&RenderedPageMetadata<'site>>
     where
         K: std::borrow::Borrow<ids::Id<CorpusEntry>>,
     {
-        match self.site.origins.get(origin.borrow()) {
-            None => None,
-            Some(dest) => self.site.writables.get(dest), // this will always be Some(...)
-        }
+        self.site.get_by_origin(origin)
+    }
+
+    pub fn pages(&self) -> impl Iterator<Item = &RenderedPageMetadata<'site>> {
+        self.site.pages()
     }
 }
 
@@ -99,10 +162,17 @@ where
     pub fn values(&mut self) -> &mut jinja::RenderContext {
         &mut self.v
     }
+
+    // renders the page's template, the page's values are consumed in the process
+    pub fn render(&mut self, renderer: &jinja::Renderer<'_, '_>) -> crate::Result<Vec<u8>> {
+        let values = std::mem::replace(&mut self.v, jinja::RenderContext::empty());
+        Ok(renderer.render_template(self.tpl, values)?.into_bytes())
+    }
 }
 
+// an index of everything that has been rendered so far
 pub struct RenderedSite<'site> {
-    writables: HashMap<ids::Id<RenderedSite<'site>>, Writable<'site>>,
+    pages: HashMap<ids::Id<RenderedSite<'site>>, RenderedPageMetadata<'site>>,
     origins: HashMap<ids::Id<CorpusEntry>, ids::Id<RenderedSite<'site>>>,
 }
 
@@ -146,7 +216,7 @@ impl<'site> RenderedPage<'site> {
         self.content.len() as u64
     }
 
-    pub fn metadata(&self) -> &RenderedPageMetadata {
+    pub fn metadata(&self) -> &RenderedPageMetadata<'_> {
         &self.meta
     }
 }
@@ -154,21 +224,29 @@ impl<'site> RenderedPage<'site> {
 impl<'env> RenderedSite<'env> {
     pub fn new() -> Self {
         Self {
-            writables: Default::default(),
+            pages: Default::default(),
             origins: Default::default(),
         }
     }
 
-    pub fn entries(
-        self,
-    ) -> impl std::iter::Iterator<Item = (ids::Id<RenderedSite<'env>>, Writable<'env>)> {
-        self.writables.into_iter()
+    pub fn pages(&self) -> impl Iterator<Item = &RenderedPageMetadata<'env>> {
+        self.pages.values()
     }
 
-    pub fn get<K>(&self, id: K) -> Option<&Writable<'env>>
+    pub fn get<K>(&self, id: K) -> Option<&RenderedPageMetadata<'env>>
     where
         K: std::borrow::Borrow<ids::Id<RenderedSite<'env>>>,
     {
-        self.writables.get(id.borrow())
+        self.pages.get(id.borrow())
+    }
+
+    pub fn get_by_origin<K>(&self, origin: K) -> Option<&RenderedPageMetadata<'env>>
+    where
+        K: std::borrow::Borrow<ids::Id<CorpusEntry>>,
+    {
+        // this will always be Some(...) if the origin is known
+        self.origins
+            .get(origin.borrow())
+            .and_then(|id| self.get(id))
     }
 }
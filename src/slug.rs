@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+
+// lowercased letters and digits from any script, whitespace becomes a dash and other punctuation
+// is dropped. dashes and underscores that were already there are kept
+pub fn slugify<S: AsRef<str>>(s: S) -> String {
+    let mut slug = String::with_capacity(s.as_ref().len());
+    let mut space = false;
+    for c in s.as_ref().trim().chars() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if !(c.is_alphanumeric() || c == '-' || c == '_') {
+            continue;
+        }
+        if space && !slug.is_empty() {
+            slug.push('-');
+        }
+        space = false;
+        slug.extend(c.to_lowercase());
+    }
+    slug
+}
+
+// hands out slugs that haven't been used yet, a repeat gets a counter appended
+#[derive(Debug, Default)]
+pub struct Slugs {
+    used: HashSet<String>,
+}
+
+impl Slugs {
+    pub fn unique<S: AsRef<str>>(&mut self, s: S) -> String {
+        let base = match slugify(s) {
+            slug if slug.is_empty() => "section".to_owned(),
+            slug => slug,
+        };
+        let mut slug = base.clone();
+        let mut n = 0;
+        while self.used.contains(&slug) {
+            n += 1;
+            slug = format!("{}-{}", base, n);
+        }
+        self.used.insert(slug.clone());
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{slugify, Slugs};
+
+    #[test]
+    fn slugifies_any_script() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Rust   2024  "), "rust-2024");
+        assert_eq!(slugify("C++ & Rust"), "c-rust");
+        assert_eq!(slugify("snake_case and-dashes"), "snake_case-and-dashes");
+        assert_eq!(slugify("Ünïcode Straße"), "ünïcode-straße");
+        assert_eq!(slugify("日本語 テスト"), "日本語-テスト");
+        assert_eq!(slugify("?!"), "");
+    }
+
+    #[test]
+    fn repeats_get_a_counter() {
+        let mut slugs = Slugs::default();
+        assert_eq!(slugs.unique("Intro"), "intro");
+        assert_eq!(slugs.unique("intro"), "intro-1");
+        assert_eq!(slugs.unique("Intro!"), "intro-2");
+        assert_eq!(slugs.unique("intro-1"), "intro-1-1");
+        assert_eq!(slugs.unique("***"), "section");
+        assert_eq!(slugs.unique(""), "section-1");
+    }
+}
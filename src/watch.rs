@@ -0,0 +1,76 @@
+use std::{
+    path,
+    sync::mpsc::{self, RecvTimeoutError},
+    time::Duration,
+};
+
+use notify::{EventKind, RecursiveMode, Watcher as _};
+
+// collapses bursts of filesystem events (editors love writing a file three times) into a single
+// notification once things have been quiet for `debounce`
+pub struct Watcher {
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    debounce: Duration,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl Watcher {
+    pub fn new<I, P>(paths: I, debounce: Duration) -> crate::Result<Watcher>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<path::Path>,
+    {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for path in paths {
+            let path = path.as_ref();
+            if !path.exists() {
+                eprintln!("not watching {}, it does not exist", path.display());
+                continue;
+            }
+            watcher.watch(path, RecursiveMode::Recursive)?;
+        }
+
+        Ok(Self {
+            events,
+            debounce,
+            _watcher: watcher,
+        })
+    }
+
+    // blocks until something changes and returns every path touched in the burst
+    pub fn wait(&self) -> crate::Result<Vec<path::PathBuf>> {
+        let mut changed = Vec::new();
+        self.collect(self.events.recv()?, &mut changed);
+
+        loop {
+            match self.events.recv_timeout(self.debounce) {
+                Ok(event) => self.collect(event, &mut changed),
+                Err(RecvTimeoutError::Timeout) if changed.is_empty() => {
+                    self.collect(self.events.recv()?, &mut changed)
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(e @ RecvTimeoutError::Disconnected) => return Err(Box::new(e)),
+            }
+        }
+
+        changed.sort();
+        changed.dedup();
+        Ok(changed)
+    }
+
+    // a failed event only loses that event, the watch carries on
+    fn collect(&self, event: notify::Result<notify::Event>, changed: &mut Vec<path::PathBuf>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("watch error: {}", e);
+                return;
+            }
+        };
+        match event.kind {
+            EventKind::Access(_) => {}
+            _ => changed.extend(event.paths),
+        }
+    }
+}
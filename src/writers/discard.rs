@@ -0,0 +1,14 @@
+use crate::site;
+
+// throws away everything it's handed, for when only rendering matters
+pub struct Discard;
+
+impl site::Writer for Discard {
+    fn write_rendered_page(&mut self, _: site::RenderedPage) -> crate::Result<()> {
+        Ok(())
+    }
+
+    fn write_static_asset(&mut self, _: site::IncludedAsset) -> crate::Result<()> {
+        Ok(())
+    }
+}
@@ -14,38 +14,19 @@ impl Files {
         Ok(Self(dir))
     }
 
+    // destinations are always files, only their parent directories are created
     fn create_path<P: AsRef<path::Path>>(&self, path: P) -> crate::Result<path::PathBuf> {
-        let mut path = self.0.join(path);
-        match path.extension() {
-            None => fs::create_dir_all(&path)?,
-            Some(_) => {
-                let filename = path.file_name().unwrap().to_os_string();
-                path.pop();
-                fs::create_dir_all(&path)?;
-                path.push(filename);
-            }
+        let path = self.0.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
-
         Ok(path)
     }
 }
 
 impl site::Writer for Files {
     fn write_static_asset(&mut self, asset: site::IncludedAsset) -> crate::Result<()> {
-        use files::Path::*;
-        let dest = self.create_path(asset.destination())?;
-        match asset.source() {
-            File(f) => {
-                fs::copy(f, dest)?;
-            }
-            Dir(d) => {
-                for path in files::Walker::walk(&d, files::RecursionBehavior::Recurse) {
-                    let name = path.file_name().unwrap().to_os_string();
-                    fs::copy(path, dest.join(name))?;
-                }
-            }
-        }
-
+        fs::copy(asset.source(), self.create_path(asset.destination())?)?;
         Ok(())
     }
 
@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    sync::{Arc, RwLock},
+};
+
+use crate::site;
+
+pub struct Served {
+    pub(crate) content: Vec<u8>,
+    pub(crate) mime: &'static str,
+}
+
+// url path -> contents, e.g. "/pages/some-article/index.html"
+pub type ServedSite = Arc<RwLock<HashMap<String, Served>>>;
+
+// keeps the whole site in memory, the served site is only replaced once the writer is flushed so
+// readers never see a partial build
+pub struct Memory {
+    staged: HashMap<String, Served>,
+    site: ServedSite,
+}
+
+impl Memory {
+    pub fn new(site: ServedSite) -> Memory {
+        Self {
+            staged: Default::default(),
+            site,
+        }
+    }
+
+    fn stage<P: AsRef<std::path::Path>>(&mut self, dest: P, content: Vec<u8>) {
+        let key = url_path(dest);
+        let mime = mime_type(&key);
+        self.staged.insert(key, Served { content, mime });
+    }
+}
+
+impl site::Writer for Memory {
+    fn write_rendered_page(&mut self, page: site::RenderedPage) -> crate::Result<()> {
+        let dest = page.metadata().url.clone().into_owned();
+        let mut content = Vec::with_capacity(page.size() as usize);
+        page.read().read_to_end(&mut content)?;
+        self.stage(dest, content);
+        Ok(())
+    }
+
+    fn write_static_asset(&mut self, asset: site::IncludedAsset) -> crate::Result<()> {
+        let content = std::fs::read(asset.source())?;
+        self.stage(asset.destination(), content);
+        Ok(())
+    }
+
+    fn flush(self: Box<Self>) -> crate::Result<()> {
+        let mut site = self.site.write().unwrap();
+        *site = self.staged;
+        Ok(())
+    }
+}
+
+fn url_path<P: AsRef<std::path::Path>>(p: P) -> String {
+    let mut key = String::from("/");
+    let parts: Vec<_> = p
+        .as_ref()
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    key.push_str(&parts.join("/"));
+    key
+}
+
+pub fn mime_type<S: AsRef<str>>(path: S) -> &'static str {
+    let ext = path.as_ref().rsplit_once('.').map(|(_, ext)| ext);
+    match ext.map(|e| e.to_ascii_lowercase()).as_deref() {
+        Some("html") | Some("htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
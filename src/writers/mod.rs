@@ -1,5 +1,9 @@
+mod discard;
 mod files;
+mod memory;
 mod tar;
 
+pub use discard::Discard;
 pub use files::Files;
+pub use memory::{Memory, ServedSite};
 pub use tar::Tar;
@@ -17,16 +17,9 @@ impl<W: Write> site::Writer for Tar<W> {
     }
 
     fn write_static_asset(&mut self, asset: site::IncludedAsset) -> crate::Result<()> {
-        match asset.source() {
-            // append_path_with_name is (src, dest)
-            files::Path::File(f) => {
-                self.archive.append_path_with_name(f, asset.destination())?;
-            }
-            // append_dir_all is (dest, src)
-            files::Path::Dir(d) => {
-                self.archive.append_dir_all(asset.destination(), d)?;
-            }
-        }
+        // append_path_with_name is (src, dest)
+        self.archive
+            .append_path_with_name(asset.source(), asset.destination())?;
         Ok(())
     }
 